@@ -0,0 +1,35 @@
+//! Read the version of the verifier library from `Cargo.lock`, since the library does not
+//! expose it. The version is given in the environment variable `VERIFIER_LIB_VERSION`
+
+use std::path::PathBuf;
+
+const VERIFIER_LIB_NAME: &str = "rust_ev_verifier_lib";
+
+/// Version of the package in the content of `Cargo.lock`
+fn locked_version(lock: &str, package: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock.lines().skip_while(|l| l.trim() != name_line).skip(1);
+    lines
+        .next()?
+        .trim()
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(str::to_string)
+}
+
+fn main() {
+    let lock_file = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_file.display());
+    let version = std::fs::read_to_string(&lock_file)
+        .ok()
+        .and_then(|lock| locked_version(&lock, VERIFIER_LIB_NAME))
+        .unwrap_or_else(|| {
+            println!(
+                "cargo:warning=Version of {} not found in {}",
+                VERIFIER_LIB_NAME,
+                lock_file.display()
+            );
+            "unknown".to_string()
+        });
+    println!("cargo:rustc-env=VERIFIER_LIB_VERSION={}", version);
+}
@@ -1,15 +1,43 @@
-use crate::CONFIG;
+use crate::{
+    error::ErrorCode,
+    persistence::StateWriter,
+    state_machine::{next_status, TransitionEvent},
+    CONFIG,
+};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
 use rust_ev_verifier_lib::{
     application_runner::ExtractDataSetResults,
     verification::{VerificationMetaDataList, VerificationPeriod},
     Config,
 };
+use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
-use strum::AsRefStr;
-use tokio::sync::RwLock;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use strum::{AsRefStr, EnumIter};
+use tokio::{
+    sync::{broadcast, oneshot, RwLock},
+    task::AbortHandle,
+};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Capacity of the channel broadcasting the events to the subscribers
+const EVENTS_CHANNEL_CAPACITY: usize = 256;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, Serialize, Deserialize)]
+/// Name of the directory, in the data directory, where the uploaded datasets are stored
+pub const UPLOAD_DIR_NAME: &str = "uploads";
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema,
+)]
 pub enum AppStatus {
     NotInitialized,
     Initialized,
@@ -21,16 +49,29 @@ pub enum AppStatus {
     Running,
     RunError,
     Finished,
+    Cancelled,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Input datasets, with their SHA-256 (lowercase hexadecimal) calculated when they are loaded
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct InputFileLocation {
+    #[schema(value_type = Option<String>)]
     pub context_zip_file: Option<PathBuf>,
+    #[schema(value_type = Option<String>)]
     pub setup_zip_file: Option<PathBuf>,
+    #[schema(value_type = Option<String>)]
     pub tally_zip_file: Option<PathBuf>,
+    #[serde(default)]
+    pub context_sha256: Option<String>,
+    #[serde(default)]
+    pub setup_sha256: Option<String>,
+    #[serde(default)]
+    pub tally_sha256: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema,
+)]
 pub enum VerificationStatusEnum {
     NotStarted,
     Running,
@@ -38,24 +79,71 @@ pub enum VerificationStatusEnum {
     FinishedWithFailures,
     FinishedWithErrors,
     FinishedWithFailureAndErrors,
+    Cancelled,
+    Excluded,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct VerificationInformation {
     pub id: String,
     pub name: String,
     pub category: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct VerificationStatus {
     pub id: String,
     pub status: VerificationStatusEnum,
     pub failures: Vec<String>,
     pub errors: Vec<String>,
+    #[serde(default)]
+    pub started_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Local>>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, AsRefStr, Serialize, Deserialize)]
+/// Strategy to run the verifications
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    AsRefStr,
+    Serialize,
+    Deserialize,
+    ValueEnum,
+    ToSchema,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RunStrategyDef {
+    /// The verifications are run concurrently
+    #[default]
+    Parallel,
+    /// The verifications are run one after the other (e.g. to debug a verification)
+    Sequential,
+}
+
+/// Event sent to the subscribers each time the state of the application changes
+#[derive(Debug, Clone, AsRefStr, Serialize, Deserialize, ToSchema)]
+#[strum(serialize_all = "snake_case")]
+#[serde(untagged)]
+pub enum AppDataEvent {
+    AppStatus {
+        app_status: AppStatus,
+        error: Option<String>,
+    },
+    VerificationStatus {
+        id: String,
+        status: VerificationStatusEnum,
+    },
+}
+
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, AsRefStr, Serialize, Deserialize, ValueEnum, ToSchema,
+)]
 #[strum(serialize_all = "lowercase")]
 pub enum VerificationPeriodDef {
     #[serde(rename = "setup")]
@@ -69,14 +157,87 @@ pub struct AppData {
     pub config: &'static Config,
     pub verfification_period: Option<VerificationPeriod>,
     pub input_file_location: InputFileLocation,
+    /// Password of the datasets. Only in memory and zeroized when dropped
+    pub dataset_password: Option<SecretString>,
     pub extracted_dataset_result: Option<ExtractDataSetResults>,
+    /// Location of the extracted datasets. Persisted, in contrary to `extracted_dataset_result`
+    pub extracted_location: Option<PathBuf>,
     pub verification_information: HashMap<String, VerificationInformation>,
     pub verification_status: HashMap<String, VerificationStatus>,
+    pub exclusions: Vec<String>,
+    pub run_strategy: Option<RunStrategyDef>,
+    pub extraction_started_at: Option<DateTime<Local>>,
+    pub extraction_finished_at: Option<DateTime<Local>>,
+    pub run_started_at: Option<DateTime<Local>>,
+    pub run_finished_at: Option<DateTime<Local>>,
     pub error: Option<String>,
+    pub error_code: Option<ErrorCode>,
+    /// Warnings not stopping the verification (e.g. a dataset changed after its loading)
+    pub warnings: Vec<String>,
+    pub task_handle: Option<AbortHandle>,
+    /// Blocking threads of the extractions and runs. Kept by a reset, since the threads of
+    /// the cancelled jobs are still working
+    pub background_jobs: BackgroundJobs,
+    event_sender: broadcast::Sender<AppDataEvent>,
+    /// Writer of the file where the state is persisted. No persistence if `None`
+    state_writer: Option<StateWriter<PersistedAppData>>,
+}
+
+/// Serializable part of [AppData], persisted in the state file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedAppData {
+    app_status: AppStatus,
+    verfification_period: Option<VerificationPeriodDef>,
+    input_file_location: InputFileLocation,
+    extracted_location: Option<PathBuf>,
+    verification_information: HashMap<String, VerificationInformation>,
+    verification_status: HashMap<String, VerificationStatus>,
+    exclusions: Vec<String>,
+    run_strategy: Option<RunStrategyDef>,
+    #[serde(default)]
+    extraction_started_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    extraction_finished_at: Option<DateTime<Local>>,
+    run_started_at: Option<DateTime<Local>>,
+    run_finished_at: Option<DateTime<Local>>,
+    error: Option<String>,
+    #[serde(default)]
+    error_code: Option<ErrorCode>,
+    #[serde(default)]
+    warnings: Vec<String>,
 }
 
 pub type AppDataLockArc = Arc<RwLock<AppData>>;
 
+/// Counter of the blocking threads (extraction or run) of the state still working
+///
+/// A cancel cannot stop these threads. They are counted until their end, so that a new
+/// extraction or run is not started while a cancelled one is still working
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundJobs(Arc<AtomicUsize>);
+
+/// Job counted in [BackgroundJobs] until the guard is dropped
+#[derive(Debug)]
+pub struct BackgroundJobGuard(Arc<AtomicUsize>);
+
+impl BackgroundJobs {
+    /// Count a new job, until the returned guard is dropped
+    pub fn start(&self) -> BackgroundJobGuard {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        BackgroundJobGuard(self.0.clone())
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for BackgroundJobGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 impl Default for AppData {
     fn default() -> Self {
         Self {
@@ -84,24 +245,43 @@ impl Default for AppData {
             config: &CONFIG,
             verfification_period: None,
             input_file_location: InputFileLocation::default(),
+            dataset_password: None,
             extracted_dataset_result: None,
+            extracted_location: None,
             verification_information: HashMap::new(),
             verification_status: HashMap::new(),
+            exclusions: vec![],
+            run_strategy: None,
+            extraction_started_at: None,
+            extraction_finished_at: None,
+            run_started_at: None,
+            run_finished_at: None,
             error: None,
+            error_code: None,
+            warnings: vec![],
+            task_handle: None,
+            background_jobs: BackgroundJobs::default(),
+            event_sender: broadcast::channel(EVENTS_CHANNEL_CAPACITY).0,
+            state_writer: None,
         }
     }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for InputFileLocation {
     fn default() -> Self {
         Self {
             context_zip_file: Default::default(),
             setup_zip_file: Default::default(),
             tally_zip_file: Default::default(),
+            context_sha256: Default::default(),
+            setup_sha256: Default::default(),
+            tally_sha256: Default::default(),
         }
     }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for VerificationStatusEnum {
     fn default() -> Self {
         VerificationStatusEnum::NotStarted
@@ -109,6 +289,16 @@ impl Default for VerificationStatusEnum {
 }
 
 impl VerificationStatusEnum {
+    /// The verification is finished with failures or errors
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self,
+            Self::FinishedWithFailures
+                | Self::FinishedWithErrors
+                | Self::FinishedWithFailureAndErrors
+        )
+    }
+
     pub fn from_has_errors_has_failures(has_errors: bool, has_failures: bool) -> Self {
         match has_errors {
             true => match has_failures {
@@ -128,7 +318,263 @@ impl AppData {
         Arc::new(RwLock::new(AppData::default()))
     }
 
-    pub fn set_with_medata(&mut self, metadata_list: &VerificationMetaDataList) {
+    /// New data persisted in the given state file
+    ///
+    /// If the file exists, the state is restored from it. A state `Extracting` or `Running`
+    /// cannot be continued, and is restored as `ExtractError`, resp. `RunError`
+    pub fn new_persisted(state_file: &Path) -> AppDataLockArc {
+        let mut data = AppData {
+            state_writer: Some(StateWriter::new(state_file)),
+            ..AppData::default()
+        };
+        if state_file.exists() {
+            match std::fs::read_to_string(state_file)
+                .map_err(|e| e.to_string())
+                .and_then(|s| {
+                    serde_json::from_str::<PersistedAppData>(&s).map_err(|e| e.to_string())
+                }) {
+                Ok(persisted) => {
+                    data.restore(persisted);
+                    info!(
+                        "State restored from {} with status {}",
+                        state_file.display(),
+                        data.app_status.as_ref()
+                    );
+                }
+                Err(e) => warn!(
+                    "State file {} cannot be read. Start with a new state: {}",
+                    state_file.display(),
+                    e
+                ),
+            }
+        }
+        Arc::new(RwLock::new(data))
+    }
+
+    fn restore(&mut self, persisted: PersistedAppData) {
+        self.app_status = persisted.app_status;
+        self.verfification_period = persisted
+            .verfification_period
+            .as_ref()
+            .map(VerificationPeriod::from);
+        self.input_file_location = persisted.input_file_location;
+        self.extracted_location = persisted.extracted_location;
+        self.verification_information = persisted.verification_information;
+        self.verification_status = persisted.verification_status;
+        self.exclusions = persisted.exclusions;
+        self.run_strategy = persisted.run_strategy;
+        self.extraction_started_at = persisted.extraction_started_at;
+        self.extraction_finished_at = persisted.extraction_finished_at;
+        self.run_started_at = persisted.run_started_at;
+        self.run_finished_at = persisted.run_finished_at;
+        self.error = persisted.error;
+        self.error_code = persisted.error_code;
+        self.warnings = persisted.warnings;
+        if let Some(status) = next_status(self.app_status, TransitionEvent::Interrupt) {
+            self.error = Some(format!(
+                "{} interrupted by a restart of the backend",
+                self.app_status.as_ref()
+            ));
+            self.error_code = Some(ErrorCode::Interrupted);
+            self.app_status = status;
+            for vs in self.verification_status.values_mut().filter(|v| {
+                v.status == VerificationStatusEnum::NotStarted
+                    || v.status == VerificationStatusEnum::Running
+            }) {
+                vs.status = VerificationStatusEnum::Cancelled;
+            }
+        }
+    }
+
+    fn to_persisted(&self) -> PersistedAppData {
+        PersistedAppData {
+            app_status: self.app_status,
+            verfification_period: self
+                .verfification_period
+                .as_ref()
+                .map(VerificationPeriodDef::from),
+            input_file_location: self.input_file_location.clone(),
+            extracted_location: self.extracted_location.clone(),
+            verification_information: self.verification_information.clone(),
+            verification_status: self.verification_status.clone(),
+            exclusions: self.exclusions.clone(),
+            run_strategy: self.run_strategy,
+            extraction_started_at: self.extraction_started_at,
+            extraction_finished_at: self.extraction_finished_at,
+            run_started_at: self.run_started_at,
+            run_finished_at: self.run_finished_at,
+            error: self.error.clone(),
+            error_code: self.error_code,
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    /// Persist the state in the state file, if any
+    ///
+    /// Only a snapshot of the state is taken. The file is written in a background thread, to
+    /// not block the runtime while the lock is held
+    pub fn persist(&self) {
+        if let Some(writer) = self.state_writer.as_ref() {
+            writer.write(self.to_persisted());
+        }
+    }
+
+    /// Remove the state file, after the pending writes. The state is not persisted anymore
+    ///
+    /// The receiver is notified when the file is removed
+    pub fn remove_state_file(&mut self) -> Option<oneshot::Receiver<()>> {
+        let writer = self.state_writer.take()?;
+        info!("State file {} removed", writer.state_file().display());
+        writer.remove();
+        Some(writer.flush())
+    }
+
+    /// The receiver is notified when the pending writes of the state file are done
+    ///
+    /// `None` if the state is not persisted
+    pub fn state_file_written(&self) -> Option<oneshot::Receiver<()>> {
+        self.state_writer.as_ref().map(|w| w.flush())
+    }
+
+    /// Reset the data to the default values
+    ///
+    /// The subscribers to the events remain subscribed and the state file remains the same
+    pub fn reset(&mut self) {
+        self.remove_uploads();
+        let event_sender = self.event_sender.clone();
+        let background_jobs = self.background_jobs.clone();
+        let state_writer = self.state_writer.take();
+        *self = Self {
+            event_sender,
+            background_jobs,
+            state_writer,
+            ..Self::default()
+        };
+    }
+
+    /// Clear the data set after the status, to go back to this status
+    ///
+    /// The error is cleared. The status itself is not changed
+    pub fn clear_after(&mut self, status: AppStatus) {
+        let step = |s: AppStatus| match s {
+            AppStatus::NotInitialized => 0,
+            AppStatus::Initialized => 1,
+            AppStatus::ContextDataSetLoaded => 2,
+            AppStatus::PeriodDataSetLoaded => 3,
+            AppStatus::Extracted => 4,
+            _ => 5,
+        };
+        let step = step(status);
+        if step < 1 {
+            self.verfification_period = None;
+        }
+        if step < 2 {
+            if let Some(path) = self.input_file_location.context_zip_file.take() {
+                self.remove_upload(&path);
+            }
+            self.input_file_location.context_sha256 = None;
+        }
+        if step < 3 {
+            for path in [
+                self.input_file_location.setup_zip_file.take(),
+                self.input_file_location.tally_zip_file.take(),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                self.remove_upload(&path);
+            }
+            self.input_file_location.setup_sha256 = None;
+            self.input_file_location.tally_sha256 = None;
+        }
+        if step < 4 {
+            self.dataset_password = None;
+            self.extracted_dataset_result = None;
+            self.extracted_location = None;
+            self.extraction_started_at = None;
+            self.extraction_finished_at = None;
+            self.warnings.clear();
+        }
+        if step < 5 {
+            self.verification_information.clear();
+            self.verification_status.clear();
+            self.exclusions.clear();
+            self.run_strategy = None;
+            self.run_started_at = None;
+            self.run_finished_at = None;
+        }
+        self.error = None;
+        self.error_code = None;
+    }
+
+    /// Directory where the uploaded datasets are stored, each one in its own subdirectory
+    pub fn upload_dir(&self) -> PathBuf {
+        self.config.data_dir_path().join(UPLOAD_DIR_NAME)
+    }
+
+    /// Delete the directory of the uploaded dataset
+    ///
+    /// Nothing is done if the dataset has not been uploaded (given with its path)
+    pub fn remove_upload(&self, path: &Path) {
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        if dir.parent() != Some(self.upload_dir().as_path()) {
+            return;
+        }
+        match std::fs::remove_dir_all(dir) {
+            Ok(_) => info!("Uploaded dataset {} deleted", path.display()),
+            Err(e) => warn!(
+                "Error deleting the uploaded dataset {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Delete the uploaded datasets. The locations of the datasets are not changed
+    pub fn remove_uploads(&self) {
+        let location = &self.input_file_location;
+        for path in [
+            &location.context_zip_file,
+            &location.setup_zip_file,
+            &location.tally_zip_file,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.remove_upload(path);
+        }
+    }
+
+    /// Subscribe to the events sent each time the state changes
+    pub fn subscribe_events(&self) -> broadcast::Receiver<AppDataEvent> {
+        self.event_sender.subscribe()
+    }
+
+    /// Event containing the actual status of the application
+    pub fn app_status_event(&self) -> AppDataEvent {
+        AppDataEvent::AppStatus {
+            app_status: self.app_status,
+            error: self.error.clone(),
+        }
+    }
+
+    /// Send the event to the subscribers and persist the new state
+    ///
+    /// Each change of the state sends an event. Nothing is sent if there is no subscriber
+    pub fn send_event(&self, event: AppDataEvent) {
+        self.persist();
+        let _ = self.event_sender.send(event);
+    }
+
+    /// Set the verifications of the period, marking the excluded verifications
+    pub fn set_with_medata(
+        &mut self,
+        metadata_list: &VerificationMetaDataList,
+        exclusions: &[String],
+    ) {
+        self.exclusions = exclusions.to_vec();
         for &id in metadata_list
             .id_list_for_period(self.verfification_period.as_ref().unwrap())
             .iter()
@@ -146,14 +592,50 @@ impl AppData {
                 id.to_string(),
                 VerificationStatus {
                     id: id.to_string(),
-                    status: VerificationStatusEnum::default(),
+                    status: match exclusions.iter().any(|e| e == id) {
+                        true => VerificationStatusEnum::Excluded,
+                        false => VerificationStatusEnum::default(),
+                    },
                     failures: vec![],
                     errors: vec![],
+                    started_at: None,
+                    finished_at: None,
                 },
             );
         }
     }
 
+    /// Ids of the verifications finished with failures or errors, sorted
+    pub fn failed_verifications(&self) -> Vec<String> {
+        let mut res = self
+            .verification_status
+            .values()
+            .filter(|v| v.status.is_failed())
+            .map(|v| v.id.clone())
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    /// Set the verifications as not started, to run them again
+    ///
+    /// The failures and errors of the previous run are removed
+    pub fn reset_verifications(&mut self, ids: &[String]) {
+        for id in ids {
+            if let Some(vs) = self.verification_status.get_mut(id) {
+                vs.status = VerificationStatusEnum::NotStarted;
+                vs.failures.clear();
+                vs.errors.clear();
+                vs.started_at = None;
+                vs.finished_at = None;
+                self.send_event(AppDataEvent::VerificationStatus {
+                    id: id.clone(),
+                    status: VerificationStatusEnum::NotStarted,
+                });
+            }
+        }
+    }
+
     pub fn not_finished(&self) -> bool {
         self.verification_status.values().any(|v| {
             v.status == VerificationStatusEnum::NotStarted
@@ -161,11 +643,62 @@ impl AppData {
         })
     }
 
+    /// The blocking thread of a cancelled extraction or run is still working
+    ///
+    /// During an extraction or a run, the counted job is the actual one
+    pub fn previous_job_finishing(&self) -> bool {
+        self.background_jobs.count() > 0
+            && !matches!(self.app_status, AppStatus::Extracting | AppStatus::Running)
+    }
+
+    /// Abort the spawned task (extraction or run)
+    ///
+    /// The verifications that are not started or running are marked as cancelled. The
+    /// blocking thread of the extraction or of the run cannot be stopped: it continues in
+    /// the background and its results are discarded. It is counted in `background_jobs`
+    /// until its end
+    pub fn cancel(&mut self) {
+        if let Some(handle) = self.task_handle.take() {
+            handle.abort();
+        }
+        let ids = self
+            .verification_status
+            .values()
+            .filter(|v| {
+                v.status == VerificationStatusEnum::NotStarted
+                    || v.status == VerificationStatusEnum::Running
+            })
+            .map(|v| v.id.clone())
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.verification_status.get_mut(&id).unwrap().status =
+                VerificationStatusEnum::Cancelled;
+            self.send_event(AppDataEvent::VerificationStatus {
+                id,
+                status: VerificationStatusEnum::Cancelled,
+            });
+        }
+    }
+
+    /// Set the verification as running, started at `started_at`
+    pub fn set_verification_running(&mut self, id: &str, started_at: DateTime<Local>) {
+        if let Some(vs) = self.verification_status.get_mut(id) {
+            vs.status = VerificationStatusEnum::Running;
+            vs.started_at = Some(started_at);
+            self.send_event(AppDataEvent::VerificationStatus {
+                id: id.to_string(),
+                status: VerificationStatusEnum::Running,
+            });
+        }
+    }
+
+    /// Set the result of the verification, finished at `finished_at`
     pub fn set_verification_status(
         &mut self,
         id: &str,
         errors: Vec<String>,
         failures: Vec<String>,
+        finished_at: DateTime<Local>,
     ) {
         let has_errors = !errors.is_empty();
         let has_failures = !failures.is_empty();
@@ -173,7 +706,13 @@ impl AppData {
             vs.status =
                 VerificationStatusEnum::from_has_errors_has_failures(has_errors, has_failures);
             vs.errors = errors;
-            vs.failures = failures
+            vs.failures = failures;
+            vs.finished_at = Some(finished_at);
+            let event = AppDataEvent::VerificationStatus {
+                id: id.to_string(),
+                status: vs.status,
+            };
+            self.send_event(event);
         }
     }
 }
@@ -195,3 +734,269 @@ impl From<&VerificationPeriodDef> for VerificationPeriod {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_with_metadata_exclusions() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut app_data = AppData {
+            verfification_period: Some(VerificationPeriod::Tally),
+            ..AppData::default()
+        };
+        let ids = metadata.id_list_for_period(&VerificationPeriod::Tally);
+        let excluded = ids[0].to_string();
+
+        app_data.set_with_medata(&metadata, std::slice::from_ref(&excluded));
+
+        assert_eq!(app_data.verification_status.len(), ids.len());
+        assert_eq!(app_data.exclusions, vec![excluded.clone()]);
+        assert_eq!(
+            app_data.verification_status.get(&excluded).unwrap().status,
+            VerificationStatusEnum::Excluded
+        );
+        assert!(app_data
+            .verification_status
+            .values()
+            .filter(|v| v.id != excluded)
+            .all(|v| v.status == VerificationStatusEnum::NotStarted));
+        assert!(app_data.not_finished());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut app_data = AppData {
+            verfification_period: Some(VerificationPeriod::Tally),
+            ..AppData::default()
+        };
+        let ids = metadata.id_list_for_period(&VerificationPeriod::Tally);
+        app_data.set_with_medata(&metadata, &[ids[0].to_string()]);
+        app_data.set_verification_running(ids[1], Local::now());
+        app_data.set_verification_running(ids[2], Local::now());
+        app_data.set_verification_status(ids[2], vec![], vec![], Local::now());
+
+        app_data.cancel();
+
+        let status = |id: &str| app_data.verification_status.get(id).unwrap().status;
+        assert_eq!(status(ids[0]), VerificationStatusEnum::Excluded);
+        assert_eq!(status(ids[1]), VerificationStatusEnum::Cancelled);
+        assert_eq!(status(ids[2]), VerificationStatusEnum::FinishedSuccessfully);
+        assert_eq!(status(ids[3]), VerificationStatusEnum::Cancelled);
+    }
+
+    #[test]
+    fn test_not_finished_all_excluded() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut app_data = AppData {
+            verfification_period: Some(VerificationPeriod::Setup),
+            ..AppData::default()
+        };
+        let ids = metadata
+            .id_list_for_period(&VerificationPeriod::Setup)
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+
+        app_data.set_with_medata(&metadata, &ids);
+
+        assert!(!app_data.not_finished());
+    }
+
+    fn state_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("rust_ev_verifier_gui_backend_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(name);
+        let _ = std::fs::remove_file(&file);
+        file
+    }
+
+    #[test]
+    fn test_persist_restore() {
+        let file = state_file("test_persist_restore.json");
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        {
+            let data = AppData::new_persisted(&file);
+            let mut data_mut = data.try_write().unwrap();
+            data_mut.verfification_period = Some(VerificationPeriod::Setup);
+            data_mut.extracted_location = Some(PathBuf::from("./data/extracted"));
+            data_mut.set_with_medata(&metadata, &[]);
+            data_mut.app_status = AppStatus::Finished;
+            data_mut.send_event(data_mut.app_status_event());
+            data_mut
+                .state_file_written()
+                .unwrap()
+                .blocking_recv()
+                .unwrap();
+        }
+        assert!(file.exists());
+        let data = AppData::new_persisted(&file);
+        let data_read = data.try_read().unwrap();
+        assert_eq!(data_read.app_status, AppStatus::Finished);
+        assert_eq!(
+            data_read.verfification_period,
+            Some(VerificationPeriod::Setup)
+        );
+        assert_eq!(
+            data_read.extracted_location,
+            Some(PathBuf::from("./data/extracted"))
+        );
+        assert_eq!(
+            data_read.verification_status.len(),
+            metadata
+                .id_list_for_period(&VerificationPeriod::Setup)
+                .len()
+        );
+        assert!(data_read.error.is_none());
+    }
+
+    #[test]
+    fn test_restore_running() {
+        let file = state_file("test_restore_running.json");
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let id = {
+            let data = AppData::new_persisted(&file);
+            let mut data_mut = data.try_write().unwrap();
+            data_mut.verfification_period = Some(VerificationPeriod::Tally);
+            data_mut.set_with_medata(&metadata, &[]);
+            let id = data_mut.verification_status.keys().next().unwrap().clone();
+            data_mut.app_status = AppStatus::Running;
+            data_mut.set_verification_running(&id, Local::now());
+            data_mut
+                .state_file_written()
+                .unwrap()
+                .blocking_recv()
+                .unwrap();
+            id
+        };
+        let data = AppData::new_persisted(&file);
+        let data_read = data.try_read().unwrap();
+        assert_eq!(data_read.app_status, AppStatus::RunError);
+        assert!(data_read.error.is_some());
+        assert_eq!(data_read.error_code, Some(ErrorCode::Interrupted));
+        assert_eq!(
+            data_read.verification_status.get(&id).unwrap().status,
+            VerificationStatusEnum::Cancelled
+        );
+        assert!(!data_read.not_finished());
+    }
+
+    #[test]
+    fn test_restore_corrupted() {
+        let file = state_file("test_restore_corrupted.json");
+        std::fs::write(&file, "not a json").unwrap();
+        let data = AppData::new_persisted(&file);
+        assert_eq!(
+            data.try_read().unwrap().app_status,
+            AppStatus::NotInitialized
+        );
+    }
+
+    #[test]
+    fn test_background_jobs() {
+        let mut app_data = AppData {
+            app_status: AppStatus::Running,
+            ..AppData::default()
+        };
+        let job = app_data.background_jobs.start();
+        assert_eq!(app_data.background_jobs.count(), 1);
+        // The job of the actual run
+        assert!(!app_data.previous_job_finishing());
+
+        app_data.cancel();
+        app_data.app_status = AppStatus::Cancelled;
+        assert!(app_data.previous_job_finishing());
+        app_data.reset();
+        assert!(app_data.previous_job_finishing());
+
+        drop(job);
+        assert_eq!(app_data.background_jobs.count(), 0);
+        assert!(!app_data.previous_job_finishing());
+    }
+
+    #[test]
+    fn test_reset_keeps_state_file() {
+        let file = state_file("test_reset_keeps_state_file.json");
+        let data = AppData::new_persisted(&file);
+        let mut data_mut = data.try_write().unwrap();
+        data_mut.app_status = AppStatus::Initialized;
+        data_mut.reset();
+        data_mut.send_event(data_mut.app_status_event());
+        data_mut
+            .state_file_written()
+            .unwrap()
+            .blocking_recv()
+            .unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert!(content.contains("NotInitialized"));
+    }
+
+    #[test]
+    fn test_clear_after() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut app_data = AppData {
+            verfification_period: Some(VerificationPeriod::Setup),
+            extracted_location: Some(PathBuf::from("./data/extracted")),
+            error: Some("error".to_string()),
+            error_code: Some(ErrorCode::RunFailed),
+            ..AppData::default()
+        };
+        app_data.input_file_location.context_zip_file = Some(PathBuf::from("context.zip"));
+        app_data.input_file_location.setup_zip_file = Some(PathBuf::from("setup.zip"));
+        app_data.set_with_medata(&metadata, &[]);
+
+        app_data.clear_after(AppStatus::Extracted);
+        assert!(app_data.extracted_location.is_some());
+        assert!(app_data.verification_status.is_empty());
+        assert!(app_data.error.is_none());
+        assert!(app_data.error_code.is_none());
+
+        app_data.clear_after(AppStatus::ContextDataSetLoaded);
+        assert!(app_data.extracted_location.is_none());
+        assert!(app_data.input_file_location.setup_zip_file.is_none());
+        assert!(app_data.input_file_location.context_zip_file.is_some());
+        assert_eq!(
+            app_data.verfification_period,
+            Some(VerificationPeriod::Setup)
+        );
+    }
+
+    #[test]
+    fn test_reset_failed_verifications() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut app_data = AppData {
+            verfification_period: Some(VerificationPeriod::Setup),
+            ..AppData::default()
+        };
+        app_data.set_with_medata(&metadata, &[]);
+        let mut ids = app_data
+            .verification_status
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        ids.sort();
+        for id in ids.iter() {
+            app_data.set_verification_status(id, vec![], vec![], Local::now());
+        }
+        app_data.set_verification_status(
+            &ids[1],
+            vec![],
+            vec!["failure".to_string()],
+            Local::now(),
+        );
+        app_data.set_verification_status(&ids[0], vec!["error".to_string()], vec![], Local::now());
+        assert!(!app_data.not_finished());
+
+        let failed = app_data.failed_verifications();
+        assert_eq!(failed, vec![ids[0].clone(), ids[1].clone()]);
+
+        app_data.reset_verifications(&failed);
+        assert!(app_data.failed_verifications().is_empty());
+        assert!(app_data.not_finished());
+        let vs = app_data.verification_status.get(&ids[1]).unwrap();
+        assert_eq!(vs.status, VerificationStatusEnum::NotStarted);
+        assert!(vs.failures.is_empty());
+    }
+}
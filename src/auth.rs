@@ -0,0 +1,140 @@
+use secrecy::{ExposeSecret, SecretString};
+use std::{io::Write, path::Path};
+use uuid::Uuid;
+
+/// Name of the file, in the data directory, containing the token of the actual launch
+pub const TOKEN_FILE_NAME: &str = "api_token";
+
+/// Bearer token expected in the header `Authorization` of the requests
+///
+/// A new token is generated at each start of the server
+#[derive(Debug, Clone)]
+pub struct AuthToken(SecretString);
+
+impl AuthToken {
+    /// Generate a random token (two UUID v4, i.e. 244 random bits)
+    pub fn generate() -> Self {
+        Self(SecretString::from(format!(
+            "{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        )))
+    }
+
+    /// Write the token in the file, readable and writable only by the owner
+    ///
+    /// The file of a previous launch is replaced
+    pub fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(path)?
+            .write_all(self.0.expose_secret().as_bytes())
+    }
+
+    /// Check the value of the header `Authorization`
+    ///
+    /// The comparison takes the same time for all the tokens of the same length
+    pub fn is_valid_header(&self, header: &str) -> bool {
+        let Some(token) = header.strip_prefix("Bearer ") else {
+            return false;
+        };
+        let expected = self.0.expose_secret().as_bytes();
+        token.len() == expected.len()
+            && token
+                .as_bytes()
+                .iter()
+                .zip(expected)
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+
+    pub fn expose(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+impl From<&str> for AuthToken {
+    fn from(value: &str) -> Self {
+        Self(SecretString::from(value))
+    }
+}
+
+/// Variable of `.env` to serve the documentation of the API (`/openapi.json` and `/docs`)
+/// without token (`true` or `false`, default `false`)
+pub const PUBLIC_DOCS_VAR: &str = "APP_PUBLIC_DOCS";
+
+/// Authentication of the requests
+///
+/// All the requests, except the health check `/`, need the token. The documentation of the
+/// API is only open if it is explicitly configured with [AuthConfig::with_public_docs]
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    token: AuthToken,
+    public_docs: bool,
+}
+
+impl AuthConfig {
+    pub fn new(token: AuthToken) -> Self {
+        Self {
+            token,
+            public_docs: false,
+        }
+    }
+
+    /// Serve the documentation of the API without token
+    pub fn with_public_docs(mut self, public_docs: bool) -> Self {
+        self.public_docs = public_docs;
+        self
+    }
+
+    pub fn token(&self) -> &AuthToken {
+        &self.token
+    }
+
+    pub fn public_docs(&self) -> bool {
+        self.public_docs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let token = AuthToken::generate();
+        assert_eq!(token.expose().len(), 64);
+        assert!(token.is_valid_header(&format!("Bearer {}", token.expose())));
+        assert!(!token.is_valid_header(token.expose()));
+        assert!(!token.is_valid_header("Bearer "));
+        assert!(!token.is_valid_header(&format!("Bearer {}", AuthToken::generate().expose())));
+        assert!(!format!("{:?}", token).contains(token.expose()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_to_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("verifier-token-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(TOKEN_FILE_NAME);
+        AuthToken::generate().write_to_file(&path).unwrap();
+        let token = AuthToken::generate();
+        token.write_to_file(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), token.expose());
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    app_data::{
+        AppData, AppDataEvent, AppDataLockArc, AppStatus, RunStrategyDef, VerificationPeriodDef,
+        VerificationStatusEnum,
+    },
+    error::AppError,
+    handler::{
+        context_dataset_from_cli, extract_handler, init_handler, period_dataset_from_cli,
+        run_handler,
+    },
+    request::{AppJson, AppJsonOrDefault, ExtractRequest, InitRequest, RunRequest},
+};
+use anyhow::{anyhow, Context};
+use axum::{Extension, Json};
+use clap::{Args, Parser, Subcommand};
+use secrecy::SecretString;
+use std::{io::BufRead, path::PathBuf, process::ExitCode};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// Exit code if a verification has failures or errors
+pub const EXIT_VERIFICATION_FAILED: u8 = 1;
+
+/// Exit code if the pipeline cannot be completed (e.g. extraction or run error)
+pub const EXIT_PIPELINE_ERROR: u8 = 2;
+
+#[derive(Parser)]
+#[command(version, about = "Backend of the Verifier GUI")]
+pub struct Cli {
+    /// Command to execute. The server is started per default
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Start the HTTP server
+    Serve,
+    /// Verify the datasets without HTTP server: init, context, period, extract and run
+    Verify(VerifyArgs),
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Verification period
+    #[arg(long, value_enum)]
+    pub period: VerificationPeriodDef,
+    /// Zip file of the context dataset
+    #[arg(long)]
+    pub context: PathBuf,
+    /// Zip file of the dataset of the period (setup or tally)
+    #[arg(long)]
+    pub period_dataset: PathBuf,
+    /// Environment variable containing the password of the datasets
+    #[arg(long, conflicts_with = "password_file")]
+    pub password_env: Option<String>,
+    /// File containing the password of the datasets (first line).
+    /// If neither the variable nor the file is given, the password is read from stdin
+    #[arg(long)]
+    pub password_file: Option<PathBuf>,
+    /// Id of a verification to exclude. Can be repeated
+    #[arg(long = "exclude", value_name = "ID")]
+    pub exclusions: Vec<String>,
+    /// Strategy to run the verifications
+    #[arg(long, value_enum, default_value_t = RunStrategyDef::Parallel)]
+    pub strategy: RunStrategyDef,
+}
+
+fn app_error(e: AppError) -> anyhow::Error {
+    anyhow!("{}: {}", e.code().as_ref(), e.message())
+}
+
+fn first_line(content: &str) -> String {
+    content.lines().next().unwrap_or_default().to_string()
+}
+
+impl VerifyArgs {
+    fn password(&self) -> anyhow::Result<SecretString> {
+        let password = match (&self.password_env, &self.password_file) {
+            (Some(var), _) => {
+                dotenvy::var(var).with_context(|| format!("Password variable {} not found", var))?
+            }
+            (None, Some(file)) => first_line(
+                &std::fs::read_to_string(file)
+                    .with_context(|| format!("Error reading the password file {:?}", file))?,
+            ),
+            (None, None) => {
+                eprintln!("Dataset password:");
+                let mut line = String::new();
+                std::io::stdin()
+                    .lock()
+                    .read_line(&mut line)
+                    .context("Error reading the password from stdin")?;
+                first_line(&line)
+            }
+        };
+        Ok(SecretString::from(password))
+    }
+}
+
+/// Print the events until the status is not the waited status anymore. Return the new status
+async fn wait_while(
+    state: &AppDataLockArc,
+    receiver: &mut Receiver<AppDataEvent>,
+    status: AppStatus,
+) -> anyhow::Result<AppStatus> {
+    loop {
+        match receiver.recv().await {
+            Ok(AppDataEvent::AppStatus { app_status, error }) => {
+                if app_status != status {
+                    if let Some(e) = error {
+                        println!("{}: {}", app_status.as_ref(), e);
+                    }
+                    return Ok(app_status);
+                }
+            }
+            Ok(AppDataEvent::VerificationStatus { id, status }) => {
+                let state_read = state.read().await;
+                let name = state_read
+                    .verification_information
+                    .get(&id)
+                    .map(|v| v.name.as_str())
+                    .unwrap_or_default();
+                println!("  {} {}: {}", id, name, status.as_ref());
+            }
+            // Some progress is lost, but not the actual status
+            Err(RecvError::Lagged(_)) => {
+                let actual = state.read().await.app_status;
+                if actual != status {
+                    return Ok(actual);
+                }
+            }
+            Err(RecvError::Closed) => return Err(anyhow!("The events channel is closed")),
+        }
+    }
+}
+
+/// Run the pipeline with the handlers and the state machine of the server
+async fn run_pipeline(args: &VerifyArgs) -> anyhow::Result<ExitCode> {
+    let password = args.password()?;
+    let state = AppData::new();
+
+    let _ = init_handler(
+        Extension(state.clone()),
+        AppJson(InitRequest {
+            period: args.period,
+        }),
+    )
+    .await
+    .map_err(app_error)?;
+    println!("Initialized for the period {}", args.period.as_ref());
+    // The datasets of the command line can be anywhere, not only in the dataset directories
+    let _ = context_dataset_from_cli(&state, &args.context)
+        .await
+        .map_err(app_error)?;
+    let Json(loaded) = period_dataset_from_cli(&state, &args.period_dataset)
+        .await
+        .map_err(app_error)?;
+    println!("Datasets loaded");
+    let location = &loaded.input_file_location;
+    for sha256 in [
+        &location.context_sha256,
+        &location.setup_sha256,
+        &location.tally_sha256,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        println!("  SHA-256: {}", sha256);
+    }
+
+    // Subscribed before each step, to receive only the events of the step
+    let mut receiver = state.read().await.subscribe_events();
+    let _ = extract_handler(
+        Extension(state.clone()),
+        AppJson(ExtractRequest { password }),
+    )
+    .await
+    .map_err(app_error)?;
+    println!("Extracting...");
+    let status = wait_while(&state, &mut receiver, AppStatus::Extracting).await?;
+    if status != AppStatus::Extracted {
+        return Ok(ExitCode::from(EXIT_PIPELINE_ERROR));
+    }
+    println!("Extracted");
+    for w in state.read().await.warnings.iter() {
+        println!("  warning: {}", w);
+    }
+
+    let mut receiver = state.read().await.subscribe_events();
+    let _ = run_handler(
+        Extension(state.clone()),
+        AppJsonOrDefault(RunRequest {
+            exclusions: args.exclusions.clone(),
+            strategy: args.strategy,
+        }),
+    )
+    .await
+    .map_err(app_error)?;
+    println!("Running...");
+    let status = wait_while(&state, &mut receiver, AppStatus::Running).await?;
+    if status != AppStatus::Finished {
+        return Ok(ExitCode::from(EXIT_PIPELINE_ERROR));
+    }
+
+    let state_read = state.read().await;
+    let failed = state_read.failed_verifications();
+    let excluded = state_read
+        .verification_status
+        .values()
+        .filter(|v| v.status == VerificationStatusEnum::Excluded)
+        .count();
+    println!(
+        "Finished: {} verifications, {} excluded, {} with failures or errors",
+        state_read.verification_status.len(),
+        excluded,
+        failed.len()
+    );
+    for id in failed.iter() {
+        let vs = state_read.verification_status.get(id).unwrap();
+        println!("  {}: {}", id, vs.status.as_ref());
+        for e in vs.errors.iter() {
+            println!("    error: {}", e);
+        }
+        for f in vs.failures.iter() {
+            println!("    failure: {}", f);
+        }
+    }
+    match failed.is_empty() {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::from(EXIT_VERIFICATION_FAILED)),
+    }
+}
+
+/// Verify the datasets without HTTP server
+///
+/// The exit code is [EXIT_VERIFICATION_FAILED] if a verification has failures or errors, and
+/// [EXIT_PIPELINE_ERROR] if the pipeline cannot be completed
+pub async fn verify(args: &VerifyArgs) -> ExitCode {
+    match run_pipeline(args).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::from(EXIT_PIPELINE_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from([
+            "backend",
+            "verify",
+            "--period",
+            "tally",
+            "--context",
+            "context.zip",
+            "--period-dataset",
+            "tally.zip",
+            "--password-env",
+            "PASSWORD",
+            "--exclude",
+            "01.01",
+            "--exclude",
+            "01.02",
+            "--strategy",
+            "sequential",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Verify(args)) => {
+                assert_eq!(args.period, VerificationPeriodDef::Tally);
+                assert_eq!(args.exclusions, vec!["01.01", "01.02"]);
+                assert_eq!(args.strategy, RunStrategyDef::Sequential);
+            }
+            _ => panic!("verify expected"),
+        }
+        assert!(Cli::try_parse_from(["backend"]).unwrap().command.is_none());
+        assert!(Cli::try_parse_from([
+            "backend",
+            "verify",
+            "--period",
+            "tally",
+            "--context",
+            "c.zip",
+            "--period-dataset",
+            "t.zip",
+            "--password-env",
+            "A",
+            "--password-file",
+            "f"
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_missing_dataset() {
+        let args = VerifyArgs {
+            period: VerificationPeriodDef::Tally,
+            // Outside the dataset directories
+            context: PathBuf::from("./Cargo.toml"),
+            period_dataset: PathBuf::from("./toto.zip"),
+            password_env: None,
+            password_file: Some(PathBuf::from("./Cargo.toml")),
+            exclusions: vec![],
+            strategy: RunStrategyDef::Parallel,
+        };
+        match run_pipeline(&args).await {
+            Err(e) => assert_eq!(e.to_string(), "FILE_NOT_FOUND: File ./toto.zip not exist"),
+            Ok(_) => panic!("Error expected"),
+        }
+        assert_eq!(verify(&args).await, ExitCode::from(EXIT_PIPELINE_ERROR));
+    }
+}
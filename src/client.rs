@@ -0,0 +1,453 @@
+//! Typed client of the API of the backend
+//!
+//! The client uses the types of the modules [request](crate::request) and
+//! [response](crate::response). The paths are the paths of [RoutePath]
+
+use crate::{
+    app_data::{AppDataEvent, AppStatus, RunStrategyDef, VerificationPeriodDef},
+    auth::AuthToken,
+    error::ErrorResponse,
+    report::ReportFormat,
+    request::{FilePathRequest, InitRequest, RunRequest, UPLOAD_FIELD_NAME},
+    response::{
+        FilesResponse, ManualChecksResponse, SessionResponse, StateMachineResponse, StatusResponse,
+        VerificationsResponse,
+    },
+    router::{RoutePath, OPENAPI_PATH, SESSIONS_PATH},
+    state_machine::StateMachineFormat,
+};
+use futures::{stream, stream::BoxStream, StreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{multipart, RequestBuilder, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::{fmt::Display, path::Path, time::Duration};
+use tokio_util::io::ReaderStream;
+
+/// Characters encoded in a segment of a path (all except the unreserved characters)
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Interval between two calls of the status while waiting for a status
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Error of the client
+#[derive(Debug)]
+pub enum ClientError {
+    /// Error returned by the backend
+    Api {
+        status: StatusCode,
+        error: ErrorResponse,
+    },
+    /// Error response without [ErrorResponse], e.g. returned by the HTTP layer (`413`, `405`)
+    UnexpectedResponse { status: StatusCode, body: String },
+    /// Error of the connection or invalid response
+    Http(reqwest::Error),
+    /// Error reading a file to upload
+    Io(std::io::Error),
+    /// The status reached is not the expected one (e.g. extraction error)
+    UnexpectedStatus {
+        expected: AppStatus,
+        actual: AppStatus,
+        error: Option<String>,
+    },
+    /// The expected status is not reached in time
+    Timeout { expected: AppStatus },
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Api { status, error } => write!(
+                f,
+                "Error {} from the backend: {}: {}",
+                status,
+                error.code.as_ref(),
+                error.message
+            ),
+            ClientError::UnexpectedResponse { status, body } => {
+                write!(f, "Error {} from the backend: {}", status, body)
+            }
+            ClientError::Http(e) => write!(f, "HTTP error: {}", e),
+            ClientError::Io(e) => write!(f, "IO error: {}", e),
+            ClientError::UnexpectedStatus {
+                expected,
+                actual,
+                error,
+            } => write!(
+                f,
+                "Status {} reached instead of {}{}",
+                actual.as_ref(),
+                expected.as_ref(),
+                error
+                    .as_ref()
+                    .map(|e| format!(": {}", e))
+                    .unwrap_or_default()
+            ),
+            ClientError::Timeout { expected } => {
+                write!(f, "Timeout waiting for the status {}", expected.as_ref())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Http(value)
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Client of the backend, for the default verification or for a session
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<AuthToken>,
+}
+
+impl Client {
+    /// New client for the backend at the url (e.g. `http://127.0.0.1:12999`)
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Client sending the bearer token of the backend (see [TOKEN_FILE_NAME](crate::auth::TOKEN_FILE_NAME))
+    pub fn with_token(self, token: AuthToken) -> Self {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Client trusting the certificate (PEM) of a backend served with TLS, e.g. a
+    /// self-signed certificate
+    pub fn with_root_certificate(self, pem: &[u8]) -> Result<Self, ClientError> {
+        let http = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(pem)?)
+            .build()?;
+        Ok(Self { http, ..self })
+    }
+
+    /// Client for the verification of the session
+    pub fn session(&self, session_id: &str) -> Self {
+        Self {
+            http: self.http.clone(),
+            base_url: format!("{}{}/{}", self.base_url, SESSIONS_PATH, session_id),
+            token: self.token.clone(),
+        }
+    }
+
+    /// Url of the backend, or of the session
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn get(&self, route: RoutePath) -> RequestBuilder {
+        self.http.get(self.url(route.as_ref()))
+    }
+
+    fn post(&self, route: RoutePath) -> RequestBuilder {
+        self.http.post(self.url(route.as_ref()))
+    }
+
+    /// Send the request. The error responses of the backend are returned as [ClientError::Api],
+    /// the other error responses as [ClientError::UnexpectedResponse]
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, ClientError> {
+        let request = match self.token.as_ref() {
+            Some(token) => request.bearer_auth(token.expose()),
+            None => request,
+        };
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await?;
+        Err(match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(error) => ClientError::Api { status, error },
+            Err(_) => ClientError::UnexpectedResponse { status, body },
+        })
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, ClientError> {
+        Ok(self.send(request).await?.json::<T>().await?)
+    }
+
+    /// The file is streamed, since the datasets can be large
+    async fn upload(&self, route: RoutePath, path: &Path) -> Result<StatusResponse, ClientError> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file = tokio::fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+        let body = reqwest::Body::wrap_stream(ReaderStream::new(file));
+        let part = multipart::Part::stream_with_length(body, length).file_name(file_name);
+        let form = multipart::Form::new().part(UPLOAD_FIELD_NAME, part);
+        self.send_json(self.post(route).multipart(form)).await
+    }
+
+    pub async fn health_check(&self) -> Result<String, ClientError> {
+        self.send_json(self.get(RoutePath::Root)).await
+    }
+
+    pub async fn status(&self) -> Result<StatusResponse, ClientError> {
+        self.send_json(self.get(RoutePath::Status)).await
+    }
+
+    pub async fn init(&self, period: VerificationPeriodDef) -> Result<StatusResponse, ClientError> {
+        self.send_json(self.post(RoutePath::Init).json(&InitRequest { period }))
+            .await
+    }
+
+    /// Directories and zip files in the dataset directories of the backend
+    ///
+    /// The dataset directories are listed if `path` is `None`
+    pub async fn files(&self, path: Option<&Path>) -> Result<FilesResponse, ClientError> {
+        let mut request = self.get(RoutePath::Files);
+        if let Some(path) = path {
+            request = request.query(&[("path", path)]);
+        }
+        self.send_json(request).await
+    }
+
+    /// Set the context dataset with a path on the machine of the backend
+    pub async fn context_dataset(&self, path: &Path) -> Result<StatusResponse, ClientError> {
+        let body = FilePathRequest {
+            path: path.to_path_buf(),
+        };
+        self.send_json(self.post(RoutePath::ContextDataset).json(&body))
+            .await
+    }
+
+    /// Upload the context dataset
+    pub async fn context_dataset_upload(&self, path: &Path) -> Result<StatusResponse, ClientError> {
+        self.upload(RoutePath::ContextDatasetUpload, path).await
+    }
+
+    /// Set the dataset of the period with a path on the machine of the backend
+    pub async fn period_dataset(&self, path: &Path) -> Result<StatusResponse, ClientError> {
+        let body = FilePathRequest {
+            path: path.to_path_buf(),
+        };
+        self.send_json(self.post(RoutePath::PeriodDataset).json(&body))
+            .await
+    }
+
+    /// Upload the dataset of the period
+    pub async fn period_dataset_upload(&self, path: &Path) -> Result<StatusResponse, ClientError> {
+        self.upload(RoutePath::PeriodDatasetUpload, path).await
+    }
+
+    pub async fn extract(&self, password: &SecretString) -> Result<StatusResponse, ClientError> {
+        let body = json!({ "password": password.expose_secret() });
+        self.send_json(self.post(RoutePath::Extract).json(&body))
+            .await
+    }
+
+    pub async fn run(
+        &self,
+        exclusions: &[String],
+        strategy: RunStrategyDef,
+    ) -> Result<StatusResponse, ClientError> {
+        let body = RunRequest {
+            exclusions: exclusions.to_vec(),
+            strategy,
+        };
+        self.send_json(self.post(RoutePath::Run).json(&body)).await
+    }
+
+    /// Run again the verifications finished with failures or errors
+    pub async fn run_failed(&self) -> Result<StatusResponse, ClientError> {
+        self.send_json(self.post(RoutePath::RunFailed)).await
+    }
+
+    /// Run again the verification
+    pub async fn run_verification(&self, id: &str) -> Result<StatusResponse, ClientError> {
+        let path = RoutePath::RunVerification.as_ref().replace(
+            ":verification_id",
+            &utf8_percent_encode(id, PATH_SEGMENT).to_string(),
+        );
+        self.send_json(self.http.post(self.url(&path))).await
+    }
+
+    /// Retry the extraction or the run. The password is only used for the extraction
+    pub async fn retry(
+        &self,
+        password: Option<&SecretString>,
+    ) -> Result<StatusResponse, ClientError> {
+        let body = match password {
+            Some(p) => json!({ "password": p.expose_secret() }),
+            None => json!({}),
+        };
+        self.send_json(self.post(RoutePath::Retry).json(&body))
+            .await
+    }
+
+    pub async fn back(&self) -> Result<StatusResponse, ClientError> {
+        self.send_json(self.post(RoutePath::Back)).await
+    }
+
+    pub async fn cancel(&self) -> Result<StatusResponse, ClientError> {
+        self.send_json(self.post(RoutePath::Cancel)).await
+    }
+
+    pub async fn reset(&self) -> Result<StatusResponse, ClientError> {
+        self.send_json(self.post(RoutePath::Reset)).await
+    }
+
+    pub async fn verifications(&self) -> Result<VerificationsResponse, ClientError> {
+        self.send_json(self.get(RoutePath::Verifications)).await
+    }
+
+    pub async fn manual_checks(&self) -> Result<ManualChecksResponse, ClientError> {
+        self.send_json(self.get(RoutePath::ManualChecks)).await
+    }
+
+    pub async fn state_machine(&self) -> Result<StateMachineResponse, ClientError> {
+        self.send_json(self.get(RoutePath::StateMachine)).await
+    }
+
+    /// State machine in the Graphviz DOT format
+    pub async fn state_machine_dot(&self) -> Result<String, ClientError> {
+        let request = self
+            .get(RoutePath::StateMachine)
+            .query(&[("format", StateMachineFormat::Dot.as_ref())]);
+        Ok(self.send(request).await?.text().await?)
+    }
+
+    /// Content of the report in the format
+    pub async fn report(&self, format: ReportFormat) -> Result<Vec<u8>, ClientError> {
+        let request = self
+            .get(RoutePath::Report)
+            .query(&[("format", format.as_ref())]);
+        Ok(self.send(request).await?.bytes().await?.to_vec())
+    }
+
+    /// Metrics in the Prometheus text format
+    pub async fn metrics(&self) -> Result<String, ClientError> {
+        Ok(self
+            .send(self.get(RoutePath::Metrics))
+            .await?
+            .text()
+            .await?)
+    }
+
+    /// Stream of the events. The first event contains the actual status
+    pub async fn events(
+        &self,
+    ) -> Result<BoxStream<'static, Result<AppDataEvent, ClientError>>, ClientError> {
+        let bytes = self
+            .send(self.get(RoutePath::Events))
+            .await?
+            .bytes_stream()
+            .boxed();
+        Ok(stream::unfold(
+            (bytes, String::new()),
+            |(mut bytes, mut buffer)| async move {
+                loop {
+                    // An event ends with an empty line. The other fields and the comments
+                    // (keep alive) are ignored
+                    if let Some(pos) = buffer.find("\n\n") {
+                        let block = buffer[..pos].to_string();
+                        buffer.drain(..pos + 2);
+                        let data = block
+                            .lines()
+                            .filter_map(|l| l.strip_prefix("data:"))
+                            .map(str::trim_start)
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        if data.is_empty() {
+                            continue;
+                        }
+                        let event = serde_json::from_str::<AppDataEvent>(&data).map_err(|e| {
+                            ClientError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                        });
+                        return Some((event, (bytes, buffer)));
+                    }
+                    match bytes.next().await {
+                        Some(Ok(chunk)) => buffer.push_str(&String::from_utf8_lossy(&chunk)),
+                        Some(Err(e)) => return Some((Err(e.into()), (bytes, buffer))),
+                        None => return None,
+                    }
+                }
+            },
+        )
+        .boxed())
+    }
+
+    /// OpenAPI document of the API
+    pub async fn openapi(&self) -> Result<serde_json::Value, ClientError> {
+        self.send_json(self.http.get(self.url(OPENAPI_PATH))).await
+    }
+
+    pub async fn create_session(&self) -> Result<SessionResponse, ClientError> {
+        self.send_json(self.http.post(self.url(SESSIONS_PATH)))
+            .await
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<String>, ClientError> {
+        self.send_json(self.http.get(self.url(SESSIONS_PATH))).await
+    }
+
+    pub async fn delete_session(&self, session_id: &str) -> Result<(), ClientError> {
+        let url = self.url(&format!("{}/{}", SESSIONS_PATH, session_id));
+        self.send(self.http.delete(url)).await.map(|_| ())
+    }
+
+    /// Wait until the extraction or the run in progress is finished, and check that the
+    /// expected status is reached
+    ///
+    /// Return [ClientError::UnexpectedStatus] if another status is reached (e.g. an error),
+    /// and [ClientError::Timeout] if the extraction or the run is not finished in time
+    pub async fn wait_for_status(
+        &self,
+        expected: AppStatus,
+        timeout: Duration,
+    ) -> Result<StatusResponse, ClientError> {
+        let wait = async {
+            loop {
+                let status = self.status().await?;
+                match status.app_status {
+                    s if s == expected => return Ok(status),
+                    AppStatus::Extracting | AppStatus::Running => {
+                        tokio::time::sleep(WAIT_POLL_INTERVAL).await
+                    }
+                    actual => {
+                        return Err(ClientError::UnexpectedStatus {
+                            expected,
+                            actual,
+                            error: status.error,
+                        })
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(Err(ClientError::Timeout { expected }))
+    }
+}
@@ -0,0 +1,171 @@
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use strum::AsRefStr;
+use tracing::error;
+use utoipa::ToSchema;
+
+/// Machine-readable code of an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, Serialize, Deserialize, ToSchema)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// A file given in the request does not exist
+    FileNotFound,
+    /// The session does not exist
+    SessionNotFound,
+    /// The route does not exist
+    RouteNotFound,
+    /// The bearer token is missing or not valid
+    Unauthorized,
+    /// The path given in the request is not in the dataset directories
+    PathNotAllowed,
+    /// The verification does not exist for the period
+    VerificationNotFound,
+    /// The route is not allowed in the actual status of the application
+    InvalidStateTransition,
+    /// A cancelled extraction or run is still working in the background
+    PreviousJobFinishing,
+    /// The request (body, query or parameters) is not valid
+    InvalidInput,
+    /// The upload of a dataset failed
+    UploadFailed,
+    /// The extraction of the datasets failed
+    ExtractionFailed,
+    /// The run of the verifications failed
+    RunFailed,
+    /// The extraction or the run has been interrupted by a restart of the backend
+    Interrupted,
+    /// The configuration of the verifier (e.g. direct trust) is missing or not valid
+    MissingConfig,
+    /// Unexpected internal error
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ErrorCode::FileNotFound
+            | ErrorCode::SessionNotFound
+            | ErrorCode::RouteNotFound
+            | ErrorCode::VerificationNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::PathNotAllowed => StatusCode::FORBIDDEN,
+            ErrorCode::InvalidStateTransition | ErrorCode::PreviousJobFinishing => {
+                StatusCode::CONFLICT
+            }
+            ErrorCode::InvalidInput
+            | ErrorCode::UploadFailed
+            | ErrorCode::ExtractionFailed
+            | ErrorCode::RunFailed
+            | ErrorCode::Interrupted => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::MissingConfig | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// Body of the responses with an error
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// Error returned by the handlers and the middlewares
+#[derive(Debug)]
+pub struct AppError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl AppError {
+    /// New error. The message is logged
+    pub fn new(code: ErrorCode, message: &str) -> Self {
+        error!("{}: {}", code.as_ref(), message);
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        (
+            self.code.status_code(),
+            Json(ErrorResponse {
+                code: self.code,
+                message: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Trait to map the error of a result to an [AppError] with a code
+pub trait ResultExt<T> {
+    /// Map the error to an [AppError] with the code, the message prefixing the original error
+    fn app_err(self, code: ErrorCode, message: &str) -> Result<T, AppError>;
+}
+
+impl<T, E> ResultExt<T> for Result<T, E>
+where
+    E: Into<anyhow::Error>,
+{
+    fn app_err(self, code: ErrorCode, message: &str) -> Result<T, AppError> {
+        self.map_err(|e| AppError::new(code, &format!("{}: {:#}", message, e.into())))
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(value: JsonRejection) -> Self {
+        AppError::new(ErrorCode::InvalidInput, &value.body_text())
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(value: QueryRejection) -> Self {
+        AppError::new(ErrorCode::InvalidInput, &value.body_text())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_code() {
+        assert_eq!(ErrorCode::FileNotFound.as_ref(), "FILE_NOT_FOUND");
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::InvalidStateTransition).unwrap(),
+            "\"INVALID_STATE_TRANSITION\""
+        );
+        assert_eq!(
+            ErrorCode::InvalidStateTransition.status_code(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn test_app_err() {
+        let e = Err::<(), _>(anyhow::anyhow!("test"))
+            .app_err(ErrorCode::MissingConfig, "Error reading")
+            .unwrap_err();
+        assert_eq!(e.code(), ErrorCode::MissingConfig);
+        assert_eq!(e.message(), "Error reading: test");
+    }
+}
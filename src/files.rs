@@ -0,0 +1,274 @@
+use crate::{
+    error::{AppError, ErrorCode},
+    response::{FileEntry, FileKind},
+};
+use chrono::{DateTime, Local};
+use lazy_static::lazy_static;
+use std::{
+    fs::Metadata,
+    path::{Component, Path, PathBuf},
+};
+use tracing::warn;
+
+/// Variable of `.env` with the directories where the datasets can be chosen, separated as in
+/// `PATH` (`:` on Unix, `;` on Windows)
+pub const DATASET_ROOTS_VAR: &str = "APP_DATASET_ROOTS";
+
+/// Directory of the datasets if [DATASET_ROOTS_VAR] is not set
+pub const DEFAULT_DATASET_ROOT: &str = "./datasets";
+
+lazy_static! {
+    /// Roots of the datasets, read once from `.env`
+    pub static ref DATASET_ROOTS: DatasetRoots = DatasetRoots::from_env();
+}
+
+/// Directories where the datasets given with a path can be found
+///
+/// The roots are canonicalized, so that a path is checked after resolving `..` and the
+/// symbolic links
+#[derive(Debug, Clone, Default)]
+pub struct DatasetRoots(Vec<PathBuf>);
+
+impl DatasetRoots {
+    /// The roots that cannot be canonicalized (e.g. not existing) are ignored with a warning
+    pub fn new(roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self(
+            roots
+                .into_iter()
+                .filter_map(|root| match root.canonicalize() {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        warn!("Dataset root {} ignored: {}", root.display(), e);
+                        None
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Read the roots from the variable [DATASET_ROOTS_VAR] of `.env`
+    pub fn from_env() -> Self {
+        let roots = dotenvy::var(DATASET_ROOTS_VAR).unwrap_or(DEFAULT_DATASET_ROOT.to_string());
+        Self::new(std::env::split_paths(&roots))
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.0
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.0.iter().any(|root| path.starts_with(root))
+    }
+
+    /// Canonical path of `path`, if it is under one of the roots
+    ///
+    /// If the path does not exist, [ErrorCode::FileNotFound] is only returned if the path is
+    /// under a root, to not reveal which files exist outside the roots
+    pub fn resolve(&self, path: &Path) -> Result<PathBuf, AppError> {
+        let not_allowed = || {
+            AppError::new(
+                ErrorCode::PathNotAllowed,
+                &format!("Path {} is not in the dataset directories", path.display()),
+            )
+        };
+        match path.canonicalize() {
+            Ok(p) if self.contains(&p) => Ok(p),
+            Ok(_) => Err(not_allowed()),
+            Err(_) => match normalize(path) {
+                Some(p) if self.contains(&p) => Err(AppError::new(
+                    ErrorCode::FileNotFound,
+                    &format!("File {} not exist", path.display()),
+                )),
+                _ => Err(not_allowed()),
+            },
+        }
+    }
+
+    /// Directories and zip files in the directory `dir` (the roots if `None`)
+    ///
+    /// The entries whose target is outside the roots (symbolic links) are not listed.
+    /// The function is blocking
+    pub fn list(&self, dir: Option<&Path>) -> Result<Vec<FileEntry>, AppError> {
+        let dir = match dir {
+            Some(dir) => self.resolve(dir)?,
+            None => {
+                return Ok(self
+                    .0
+                    .iter()
+                    .filter_map(|root| {
+                        let metadata = root.metadata().ok()?;
+                        Some(file_entry(
+                            root.to_string_lossy().to_string(),
+                            root.clone(),
+                            FileKind::Directory,
+                            &metadata,
+                        ))
+                    })
+                    .collect())
+            }
+        };
+        let read_dir = std::fs::read_dir(&dir).map_err(|e| {
+            AppError::new(
+                ErrorCode::InvalidInput,
+                &format!("Error reading the directory {}: {}", dir.display(), e),
+            )
+        })?;
+        let mut entries = read_dir
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let target = entry.path().canonicalize().ok()?;
+                if !self.contains(&target) {
+                    return None;
+                }
+                let metadata = target.metadata().ok()?;
+                let kind = if metadata.is_dir() {
+                    FileKind::Directory
+                } else if metadata.is_file()
+                    && entry
+                        .path()
+                        .extension()
+                        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+                {
+                    FileKind::Zip
+                } else {
+                    return None;
+                };
+                Some(file_entry(
+                    entry.file_name().to_string_lossy().to_string(),
+                    entry.path(),
+                    kind,
+                    &metadata,
+                ))
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+        Ok(entries)
+    }
+}
+
+/// Canonical path of the dataset `path`, without checking the roots
+///
+/// Only for the paths given on the command line, whose user has access to the files anyway
+pub fn canonical_dataset_path(path: &Path) -> Result<PathBuf, AppError> {
+    match path.canonicalize() {
+        Ok(p) if p.is_file() => Ok(p),
+        _ => Err(AppError::new(
+            ErrorCode::FileNotFound,
+            &format!("File {} not exist", path.display()),
+        )),
+    }
+}
+
+fn file_entry(name: String, path: PathBuf, kind: FileKind, metadata: &Metadata) -> FileEntry {
+    FileEntry {
+        name,
+        path,
+        kind,
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(DateTime::<Local>::from),
+    }
+}
+
+/// Absolute path of a not existing file, with `.` and `..` removed and its existing ancestor
+/// canonicalized
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut lexical = PathBuf::new();
+    for component in std::path::absolute(path).ok()?.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            c => lexical.push(c),
+        }
+    }
+    let mut rest = vec![];
+    let mut existing = lexical.as_path();
+    loop {
+        if let Ok(p) = existing.canonicalize() {
+            return Some(rest.into_iter().rev().fold(p, |acc, c| acc.join(c)));
+        }
+        rest.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use uuid::Uuid;
+
+    /// Directory with a root `root` containing `sub/a.zip`, `b.zip` and `c.txt`, and a file
+    /// `outside.zip` next to the root
+    fn create_tree() -> (PathBuf, DatasetRoots) {
+        let dir = std::env::temp_dir().join(format!("verifier-files-{}", Uuid::new_v4()));
+        let root = dir.join("root");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("a.zip"), "a").unwrap();
+        std::fs::write(root.join("b.zip"), "bb").unwrap();
+        std::fs::write(root.join("c.txt"), "c").unwrap();
+        std::fs::write(dir.join("outside.zip"), "o").unwrap();
+        (dir, DatasetRoots::new([root, PathBuf::from("./toto")]))
+    }
+
+    #[test]
+    fn test_resolve() {
+        let (dir, roots) = create_tree();
+        let root = dir.join("root");
+        assert_eq!(roots.roots().len(), 1);
+        assert!(roots.resolve(&root.join("b.zip")).is_ok());
+        assert!(roots
+            .resolve(&root.join("sub").join("..").join("b.zip"))
+            .is_ok());
+        let code = |p: &Path| roots.resolve(p).unwrap_err().code();
+        assert_eq!(code(&root.join("toto.zip")), ErrorCode::FileNotFound);
+        assert_eq!(
+            code(&root.join("..").join("outside.zip")),
+            ErrorCode::PathNotAllowed
+        );
+        assert_eq!(
+            code(&root.join("..").join("toto.zip")),
+            ErrorCode::PathNotAllowed
+        );
+        assert_eq!(code(&dir.join("rootx")), ErrorCode::PathNotAllowed);
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("outside.zip"), root.join("link.zip")).unwrap();
+            assert_eq!(code(&root.join("link.zip")), ErrorCode::PathNotAllowed);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_canonical_dataset_path() {
+        let (dir, _) = create_tree();
+        let outside = dir.join("root").join("..").join("outside.zip");
+        assert_eq!(
+            canonical_dataset_path(&outside).unwrap(),
+            dir.canonicalize().unwrap().join("outside.zip")
+        );
+        let code = |p: &Path| canonical_dataset_path(p).unwrap_err().code();
+        assert_eq!(code(&dir.join("toto.zip")), ErrorCode::FileNotFound);
+        assert_eq!(code(&dir.join("root")), ErrorCode::FileNotFound);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_list() {
+        let (dir, roots) = create_tree();
+        let root = dir.join("root");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, root.join("escape")).unwrap();
+        let entries = roots.list(None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, FileKind::Directory);
+        let entries = roots.list(Some(&root)).unwrap();
+        let names = entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["sub", "b.zip"]);
+        assert_eq!(entries[1].size, 2);
+        assert!(entries[1].modified.is_some());
+        assert_eq!(roots.list(Some(&root.join("sub"))).unwrap().len(), 1);
+        assert!(roots.list(Some(&dir)).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
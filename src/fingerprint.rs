@@ -0,0 +1,135 @@
+use crate::app_data::InputFileLocation;
+use anyhow::anyhow;
+use openssl::sha::Sha256;
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Size of the buffer to read the files
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// SHA-256 of the file, in lowercase hexadecimal
+///
+/// The file is read by chunks, so that the large datasets are not loaded in memory. The
+/// function is blocking
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+    Ok(hasher
+        .finish()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// SHA-256 of the file, calculated in a blocking thread to not block the async runtime
+pub async fn sha256_file_async(path: &Path) -> anyhow::Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| anyhow!("Error in the thread of the fingerprint: {}", e))?
+        .map_err(|e| anyhow!(e))
+}
+
+/// Check that the input files have not changed since they have been loaded
+///
+/// Return a warning for each file whose SHA-256 differs from the recorded one, or that
+/// cannot be read anymore. The function is blocking
+pub fn check_input_files(location: &InputFileLocation) -> Vec<String> {
+    let files: [(&str, &Option<PathBuf>, &Option<String>); 3] = [
+        (
+            "context",
+            &location.context_zip_file,
+            &location.context_sha256,
+        ),
+        ("setup", &location.setup_zip_file, &location.setup_sha256),
+        ("tally", &location.tally_zip_file, &location.tally_sha256),
+    ];
+    files
+        .into_iter()
+        .filter_map(|(kind, path, expected)| match (path, expected) {
+            (Some(path), Some(expected)) => match sha256_file(path) {
+                Ok(actual) if &actual == expected => None,
+                Ok(actual) => Some(format!(
+                    "The {} dataset {} has changed since its loading (SHA-256 {} instead of {})",
+                    kind,
+                    path.display(),
+                    actual,
+                    expected
+                )),
+                Err(e) => Some(format!(
+                    "The {} dataset {} cannot be read to check its SHA-256: {}",
+                    kind,
+                    path.display(),
+                    e
+                )),
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use uuid::Uuid;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[tokio::test]
+    async fn test_sha256() {
+        let path = std::env::temp_dir().join(format!("verifier-sha-{}", Uuid::new_v4()));
+        std::fs::write(&path, "abc").unwrap();
+        assert_eq!(sha256_file(&path).unwrap(), ABC_SHA256);
+        assert_eq!(sha256_file_async(&path).await.unwrap(), ABC_SHA256);
+        // Larger than the buffer
+        std::fs::write(&path, vec![b'a'; BUFFER_SIZE * 2 + 10]).unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            hex_sha256(&vec![b'a'; BUFFER_SIZE * 2 + 10])
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(sha256_file_async(&path).await.is_err());
+    }
+
+    fn hex_sha256(data: &[u8]) -> String {
+        openssl::sha::sha256(data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_input_files() {
+        let path = std::env::temp_dir().join(format!("verifier-sha-{}", Uuid::new_v4()));
+        std::fs::write(&path, "abc").unwrap();
+        let location = InputFileLocation {
+            context_zip_file: Some(path.clone()),
+            context_sha256: Some(ABC_SHA256.to_string()),
+            tally_zip_file: Some(PathBuf::from("./toto.zip")),
+            ..Default::default()
+        };
+        let warnings = check_input_files(&location);
+        assert_eq!(warnings.len(), 0);
+
+        std::fs::write(&path, "abcd").unwrap();
+        let location = InputFileLocation {
+            tally_sha256: Some(ABC_SHA256.to_string()),
+            ..location
+        };
+        let warnings = check_input_files(&location);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("context dataset"));
+        assert!(warnings[1].contains("cannot be read"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,45 @@
+use crate::app_data::{AppDataEvent, AppDataLockArc};
+use axum::{
+    response::sse::{Event, KeepAlive, Sse},
+    Extension,
+};
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+fn sse_event(event: &AppDataEvent) -> Result<Event, axum::Error> {
+    Event::default().event(event.as_ref()).json_data(event)
+}
+
+/// Stream of the events (Server-Sent Events)
+///
+/// The first event contains the actual status of the application. Then an event
+/// is sent each time the status of the application or of a verification changes.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "status",
+    responses(
+        (status = 200, description = "Server-Sent Events, the data of each event is an AppDataEvent", content_type = "text/event-stream", body = AppDataEvent)
+    )
+)]
+pub async fn events_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let (first_event, receiver) = {
+        let state_read = state.read().await;
+        (state_read.app_status_event(), state_read.subscribe_events())
+    };
+    debug!("New subscriber to the events");
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((sse_event(&event), receiver)),
+                Err(RecvError::Lagged(n)) => warn!("Subscriber lagged: {} events skipped", n),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream::once(async move { sse_event(&first_event) }).chain(events))
+        .keep_alive(KeepAlive::default())
+}
@@ -1,41 +1,151 @@
-use super::{get_status_response, update_status, update_with_error};
+use super::{
+    check_no_previous_job, get_status_response, set_status, update_status, update_with_error,
+};
 use crate::{
-    app_data::{AppData, AppDataLockArc, AppStatus, InputFileLocation},
+    app_data::{AppData, AppDataLockArc, AppStatus, BackgroundJobGuard, InputFileLocation},
+    error::{AppError, ErrorCode, ErrorResponse},
+    fingerprint::check_input_files,
+    metrics::METRICS,
+    request::{AppJson, ExtractRequest},
     response::StatusResponse,
-    AppError,
+    state_machine::{checked_next_status, TransitionEvent},
 };
-use anyhow::anyhow;
-use axum::{extract::State, Json};
+use axum::{Extension, Json};
+use chrono::Local;
+use lazy_static::lazy_static;
 use rust_ev_verifier_lib::{
     application_runner::ExtractDataSetResults, verification::VerificationPeriod, Config,
 };
-use tracing::{error, info, instrument};
+use secrecy::{ExposeSecret, SecretString};
+use std::path::PathBuf;
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Duration},
+};
+use tracing::{debug, info, instrument, warn};
+
+lazy_static! {
+    /// Lock serializing the extractions of the sessions, containing the second of the last start
+    ///
+    /// The library extracts in a directory named with the current time (in seconds) and uses
+    /// a common temporary directory. Two extractions must neither run concurrently nor start
+    /// in the same second
+    static ref EXTRACTION_LOCK: Mutex<i64> = Mutex::new(0);
+}
+
+/// Directory of an extraction, deleted when the guard is dropped without being kept
+///
+/// The guard is returned with the result of the blocking thread. If the task has been aborted
+/// (cancel), the result is dropped by tokio and the directory is deleted
+struct ExtractionDirGuard(Option<PathBuf>);
+
+impl ExtractionDirGuard {
+    fn keep(mut self) {
+        self.0 = None;
+    }
+}
 
-#[instrument(skip(state, config))]
+impl Drop for ExtractionDirGuard {
+    fn drop(&mut self) {
+        if let Some(dir) = self.0.take() {
+            match std::fs::remove_dir_all(&dir) {
+                Ok(_) => info!("Discarded extraction {} deleted", dir.display()),
+                Err(e) => warn!(
+                    "Error deleting the discarded extraction {}: {}",
+                    dir.display(),
+                    e
+                ),
+            }
+        }
+    }
+}
+
+#[instrument(skip(state, password, config, job))]
 async fn extract_fn(
     state: AppDataLockArc,
     period: VerificationPeriod,
     file_location: InputFileLocation,
-    password: String,
+    password: SecretString,
     config: &'static Config,
+    job: BackgroundJobGuard,
 ) {
+    let mut last_start = match EXTRACTION_LOCK.try_lock() {
+        Ok(guard) => guard,
+        Err(_) => {
+            info!("Wait for the end of the extraction of another session");
+            EXTRACTION_LOCK.lock().await
+        }
+    };
+    while Local::now().timestamp() <= *last_start {
+        debug!("Wait for the next second to start the extraction");
+        sleep(Duration::from_millis(100)).await;
+    }
+    *last_start = Local::now().timestamp();
+    // The datasets are read again, to warn if they have changed since their loading
+    let location = file_location.clone();
+    let warnings = tokio::task::spawn_blocking(move || check_input_files(&location))
+        .await
+        .unwrap_or_else(|e| {
+            vec![format!(
+                "Error checking the SHA-256 of the datasets: {:?}",
+                e
+            )]
+        });
+    for w in warnings.iter() {
+        warn!("{}", w);
+    }
+    {
+        let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+        if state_mut.app_status != AppStatus::Extracting {
+            info!("Extraction cancelled before starting");
+            return;
+        }
+        state_mut.extraction_started_at = Some(Local::now());
+        state_mut.extraction_finished_at = None;
+        state_mut.warnings = warnings;
+    }
     info!("Extraction started");
-    let extracted = match ExtractDataSetResults::extract_datasets(
-        period,
-        file_location.context_zip_file.unwrap().as_path(),
-        file_location.setup_zip_file.as_deref(),
-        file_location.tally_zip_file.as_deref(),
-        &password,
-        config,
-    ) {
+    // The extraction is blocking and runs for minutes. It must not block the async runtime.
+    // The lock and the job are moved in the thread, since the thread runs to the end even if
+    // the task is aborted
+    let (extracted, dir_guard) = match tokio::task::spawn_blocking(move || {
+        let _last_start = last_start;
+        let _job = job;
+        ExtractDataSetResults::extract_datasets(
+            period,
+            file_location.context_zip_file.unwrap().as_path(),
+            file_location.setup_zip_file.as_deref(),
+            file_location.tally_zip_file.as_deref(),
+            password.expose_secret(),
+            config,
+        )
+        .map(|res| {
+            let dir_guard = ExtractionDirGuard(Some(res.location().to_path_buf()));
+            (res, dir_guard)
+        })
+        .map_err(|e| format!("Problem extracting the datasets: {:?}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Error in the thread of the extraction: {:?}", e)))
+    {
         Ok(res) => res,
         Err(e) => {
             let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
-            update_with_error(
+            if state_mut.app_status != AppStatus::Extracting {
+                info!("Extraction cancelled. Error ignored: {}", e);
+                return;
+            }
+            state_mut.extraction_finished_at = Some(Local::now());
+            if update_with_error(
                 &mut state_mut,
-                AppStatus::ExtractError,
-                format!("Problem extracting the datasets: {:?}", e).as_str(),
-            );
+                TransitionEvent::ExtractionFailed,
+                ErrorCode::ExtractionFailed,
+                &e,
+            )
+            .is_ok()
+            {
+                METRICS.observe_extraction(&state_mut);
+            }
             return;
         }
     };
@@ -44,29 +154,78 @@ async fn extract_fn(
         extracted.location().to_str().unwrap()
     );
     let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    if state_mut.app_status != AppStatus::Extracting {
+        info!("Extraction cancelled. Result ignored");
+        // The directory is deleted outside the runtime
+        drop(state_mut);
+        let _ = tokio::task::spawn_blocking(move || drop((extracted, dir_guard))).await;
+        return;
+    }
+    dir_guard.keep();
+    state_mut.extraction_finished_at = Some(Local::now());
+    state_mut.extracted_location = Some(extracted.location().to_path_buf());
     state_mut.extracted_dataset_result = Some(extracted);
-    update_status(&mut state_mut, AppStatus::Extracted);
+    if update_status(&mut state_mut, TransitionEvent::ExtractionSucceeded).is_ok() {
+        METRICS.observe_extraction(&state_mut);
+    }
 }
 
-pub async fn extract_handler(
-    State(state): State<AppDataLockArc>,
-) -> Result<Json<StatusResponse>, AppError> {
-    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+/// Spawn the extraction of the datasets with the inputs of the state
+///
+/// The status is not changed. Error if a cancelled extraction or run is still working
+pub(super) fn start_extraction(
+    state: &AppDataLockArc,
+    state_mut: &mut AppData,
+    password: SecretString,
+) -> Result<(), AppError> {
+    check_no_previous_job(state_mut)?;
     let status_spawn = state.clone();
-    let period = state_mut.verfification_period.unwrap().clone();
+    let period = state_mut.verfification_period.unwrap();
     let file_location = state_mut.input_file_location.clone();
     let config = state_mut.config;
-    let password = dotenvy::var("APP_VERIFIER_DATASET_PASSWORD").map_err(|e| {
-        error!(
-            "port (APP_VERIFIER_DATASET_PASSWORD) not found in .env {}",
-            e
-        );
-        AppError::from(anyhow!(e))
-    })?;
-    println!("{}", password);
-    tokio::spawn(
-        async move { extract_fn(status_spawn, period, file_location, password, config).await },
-    );
-    update_status(&mut state_mut, AppStatus::Extracting);
+    let job = state_mut.background_jobs.start();
+    state_mut.dataset_password = Some(password.clone());
+    let handle = tokio::spawn(async move {
+        extract_fn(status_spawn, period, file_location, password, config, job).await
+    });
+    state_mut.task_handle = Some(handle.abort_handle());
+    Ok(())
+}
+
+/// Start the extraction of the datasets with their password
+#[utoipa::path(
+    post,
+    path = "/extract",
+    tag = "workflow",
+    request_body = ExtractRequest,
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status, or cancelled job still working", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn extract_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    AppJson(payload): AppJson<ExtractRequest>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::StartExtraction)?;
+    start_extraction(&state, &mut state_mut, payload.password)?;
+    set_status(&mut state_mut, status);
     Ok(get_status_response(&state_mut))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extraction_dir_guard() {
+        let dir = std::env::temp_dir().join("test_extraction_dir_guard");
+        std::fs::create_dir_all(dir.join("context")).unwrap();
+        ExtractionDirGuard(Some(dir.clone())).keep();
+        assert!(dir.exists());
+        drop(ExtractionDirGuard(Some(dir.clone())));
+        assert!(!dir.exists());
+    }
+}
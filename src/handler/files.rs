@@ -0,0 +1,37 @@
+use crate::{
+    error::{AppError, ErrorCode, ErrorResponse, ResultExt},
+    files::DATASET_ROOTS,
+    request::{AppQuery, FilesRequest},
+    response::FilesResponse,
+};
+use axum::Json;
+
+/// Directories and zip files in the dataset directories (see `APP_DATASET_ROOTS`)
+#[utoipa::path(
+    get,
+    path = "/files",
+    tag = "workflow",
+    params(FilesRequest),
+    responses(
+        (status = 200, description = "Content of the directory", body = FilesResponse),
+        (status = 403, description = "Directory not in the dataset directories", body = ErrorResponse),
+        (status = 404, description = "Directory not found", body = ErrorResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn files_handler(
+    AppQuery(payload): AppQuery<FilesRequest>,
+) -> Result<Json<FilesResponse>, AppError> {
+    let path = payload.path.clone();
+    let entries = tokio::task::spawn_blocking(move || DATASET_ROOTS.list(path.as_deref()))
+        .await
+        .app_err(
+            ErrorCode::InternalError,
+            "Error in the task listing the files",
+        )??;
+    Ok(Json(FilesResponse {
+        path: payload.path,
+        entries,
+    }))
+}
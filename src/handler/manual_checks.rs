@@ -0,0 +1,149 @@
+use crate::{
+    app_data::{AppDataLockArc, VerificationPeriodDef},
+    error::{AppError, ErrorCode, ErrorResponse, ResultExt},
+    response::{
+        BallotBoxManualChecks, ContestManualChecks, ElectionEventManualChecks, ManualChecksResponse,
+    },
+};
+use anyhow::anyhow;
+use axum::{Extension, Json};
+use rust_ev_crypto_primitives::EncodeTrait;
+use rust_ev_verifier_lib::{
+    application_runner::ExtractDataSetResults,
+    file_structure::{ContextDirectory, ContextDirectoryTrait},
+    Config,
+};
+use std::{collections::HashMap, path::Path};
+use tracing::{debug, instrument};
+
+/// Kinds of the datasets, as defined in the verifier library
+const DATASET_KINDS: [&str; 3] = ["context", "setup", "tally"];
+
+/// Fingerprints of the certificates of the direct trust keystore. The function is blocking
+fn direct_trust_fingerprints(config: &'static Config) -> anyhow::Result<HashMap<String, String>> {
+    config
+        .keystore()?
+        .fingerprints()?
+        .iter()
+        .map(|(ca, fingerprint)| {
+            fingerprint
+                .base16_encode()
+                .map(|f| (ca.to_string(), f))
+                .map_err(|_| anyhow!("Error encoding the fingerprint of {}", ca))
+        })
+        .collect()
+}
+
+fn dataset_fingerprints(extracted: &ExtractDataSetResults) -> HashMap<String, String> {
+    DATASET_KINDS
+        .iter()
+        .filter_map(|&kind| {
+            let md = extracted.dataset_metadata(&kind.parse().ok()?)?;
+            Some((kind.to_string(), md.fingerprint_str()))
+        })
+        .collect()
+}
+
+/// Data of the election event in the extracted context. The function is blocking
+fn election_event_manual_checks(location: &Path) -> anyhow::Result<ElectionEventManualChecks> {
+    let context_dir = ContextDirectory::new(location);
+    let ee_context = context_dir
+        .election_event_context_payload()?
+        .election_event_context;
+    let ee_config = context_dir.election_event_configuration()?;
+    Ok(ElectionEventManualChecks {
+        election_event_id: ee_context.election_event_id,
+        election_event_alias: ee_context.election_event_alias,
+        election_event_description: ee_context.election_event_description,
+        start_time: ee_context.start_time.to_string(),
+        finish_time: ee_context.finish_time.to_string(),
+        contest: ContestManualChecks {
+            file_date: ee_config.header.file_date.clone(),
+            voter_total: ee_config.header.voter_total,
+            number_of_votes: ee_config.contest.votes.iter().ok().map(|it| it.count()),
+            number_of_election_groups: ee_config
+                .contest
+                .election_groups
+                .iter()
+                .ok()
+                .map(|it| it.count()),
+        },
+        ballot_boxes: ee_context
+            .verification_card_set_contexts
+            .into_iter()
+            .map(|vcs| BallotBoxManualChecks {
+                ballot_box_id: vcs.ballot_box_id,
+                verification_card_set_id: vcs.verification_card_set_id,
+                verification_card_set_alias: vcs.verification_card_set_alias,
+                verification_card_set_description: vcs.verification_card_set_description,
+                test_ballot_box: vcs.test_ballot_box,
+                number_of_voting_cards: vcs.number_of_voting_cards,
+                start_time: vcs.ballot_box_start_time.to_string(),
+                finish_time: vcs.ballot_box_finish_time.to_string(),
+            })
+            .collect(),
+    })
+}
+
+/// Data of the extracted datasets to check manually
+#[utoipa::path(
+    get,
+    path = "/manual-checks",
+    tag = "results",
+    responses(
+        (status = 200, description = "Data of the manual checks", body = ManualChecksResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 500, description = "Error reading the extracted datasets", body = ErrorResponse)
+    )
+)]
+#[instrument(skip(state))]
+pub async fn manual_checks_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Json<ManualChecksResponse>, AppError> {
+    // The files are read in a blocking thread, after releasing the lock of the state
+    let (location, config, period, dataset_fingerprints) = {
+        let state_read = state.read().await;
+        (
+            state_read.extracted_location.clone(),
+            state_read.config,
+            state_read.verfification_period,
+            // The metadata of the datasets are not persisted, and then not available after a
+            // restart
+            state_read
+                .extracted_dataset_result
+                .as_ref()
+                .map(dataset_fingerprints)
+                .unwrap_or_default(),
+        )
+    };
+    let (election_event, direct_trust) = tokio::task::spawn_blocking(move || {
+        (
+            location.map(|l| election_event_manual_checks(&l)),
+            direct_trust_fingerprints(config),
+        )
+    })
+    .await
+    .app_err(
+        ErrorCode::InternalError,
+        "Error in the thread reading the manual checks",
+    )?;
+    let election_event = match election_event {
+        Some(res) => Some(res.app_err(
+            ErrorCode::InternalError,
+            "Error reading the election event of the extracted context",
+        )?),
+        None => {
+            debug!("Datasets not extracted yet. Only the direct trust fingerprints are delivered");
+            None
+        }
+    };
+    Ok(Json(ManualChecksResponse {
+        verfification_period: period.map(|v| VerificationPeriodDef::from(&v)),
+        direct_trust_fingerprints: direct_trust.app_err(
+            ErrorCode::MissingConfig,
+            "Error reading the direct trust fingerprints",
+        )?,
+        election_event,
+        dataset_fingerprints,
+    }))
+}
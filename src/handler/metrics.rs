@@ -0,0 +1,24 @@
+use crate::{app_data::AppDataLockArc, error::AppError, metrics::METRICS};
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use prometheus::TEXT_FORMAT;
+
+/// Metrics in the Prometheus text format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "status",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain", body = String)
+    )
+)]
+pub async fn metrics_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Response, AppError> {
+    let state_read = state.read().await;
+    let text = METRICS.render(&state_read)?;
+    Ok(([(header::CONTENT_TYPE, TEXT_FORMAT)], text).into_response())
+}
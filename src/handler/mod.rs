@@ -1,20 +1,56 @@
+mod events;
 mod extract;
+mod files;
+mod manual_checks;
+mod metrics;
+mod openapi;
+mod report;
+mod retry;
 mod run;
 mod send_file;
+mod session;
+mod state_machine;
+mod verifications;
 
+pub use events::events_handler;
 pub use extract::extract_handler;
-pub use run::run_handler;
-pub use send_file::{context_dataset_handler, period_dataset_handler};
+pub use files::files_handler;
+pub use manual_checks::manual_checks_handler;
+pub use metrics::metrics_handler;
+pub use openapi::api_doc;
+pub use report::report_handler;
+pub use retry::{back_handler, retry_handler};
+pub use run::{run_failed_handler, run_handler, run_verification_handler};
+pub use send_file::{
+    context_dataset_from_cli, context_dataset_handler, context_dataset_upload_handler,
+    period_dataset_from_cli, period_dataset_handler, period_dataset_upload_handler,
+    MAX_UPLOAD_SIZE,
+};
+pub use session::{create_session_handler, delete_session_handler, list_sessions_handler};
+pub use state_machine::state_machine_handler;
+pub use verifications::verifications_handler;
 
 use crate::{
     app_data::{AppData, AppDataLockArc, AppStatus},
-    request::InitRequest,
+    error::{AppError, ErrorCode, ErrorResponse},
+    request::{AppJson, InitRequest},
     response::StatusResponse,
+    state_machine::{checked_next_status, TransitionEvent},
 };
-use axum::{extract::State, Json};
+use axum::{Extension, Json};
 use rust_ev_verifier_lib::verification::VerificationPeriod;
 use tracing::{error, info};
 
+/// Health check
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "status",
+    security(()),
+    responses(
+        (status = 200, description = "The server is living", body = String)
+    )
+)]
 pub async fn health_check_handler() -> Json<String> {
     Json("Server is living".to_string())
 }
@@ -23,40 +59,128 @@ fn get_status_response(app_data: &AppData) -> Json<StatusResponse> {
     Json(StatusResponse::from(app_data))
 }
 
-fn update_status(data_mut: &mut AppData, status: AppStatus) {
+fn set_status(data_mut: &mut AppData, status: AppStatus) {
     data_mut.app_status = status;
     info!("Status set to {}", status.as_ref());
+    data_mut.send_event(data_mut.app_status_event());
+}
+
+/// Refuse to start an extraction or a run while a cancelled one is still working
+///
+/// Waiting silently would block the request, and the two jobs would overlap
+fn check_no_previous_job(data: &AppData) -> Result<(), AppError> {
+    match data.previous_job_finishing() {
+        true => Err(AppError::new(
+            ErrorCode::PreviousJobFinishing,
+            "A cancelled extraction or run is still working in the background. Retry after its end",
+        )),
+        false => Ok(()),
+    }
+}
+
+/// Update the status with the event, according to the transitions of the state machine
+///
+/// The status is not changed if the transition is not allowed. The error is logged
+fn update_status(data_mut: &mut AppData, event: TransitionEvent) -> Result<(), AppError> {
+    let status = checked_next_status(data_mut.app_status, event)?;
+    set_status(data_mut, status);
+    Ok(())
 }
 
-fn update_with_error(data_mut: &mut AppData, status: AppStatus, error: &str) {
-    error!("{}", error);
+fn update_with_error(
+    data_mut: &mut AppData,
+    event: TransitionEvent,
+    code: ErrorCode,
+    error: &str,
+) -> Result<(), AppError> {
+    let status = checked_next_status(data_mut.app_status, event)?;
+    error!("{}: {}", code.as_ref(), error);
     data_mut.error = Some(error.to_string());
-    update_status(data_mut, status);
+    data_mut.error_code = Some(code);
+    set_status(data_mut, status);
+    Ok(())
 }
 
-pub async fn status_handler(State(state): State<AppDataLockArc>) -> Json<StatusResponse> {
+/// Actual status of the application
+#[utoipa::path(
+    get,
+    path = "/status",
+    tag = "status",
+    responses(
+        (status = 200, description = "Actual status", body = StatusResponse)
+    )
+)]
+pub async fn status_handler(Extension(state): Extension<AppDataLockArc>) -> Json<StatusResponse> {
     let state_read = state.read().await;
     get_status_response(&state_read)
 }
 
+/// Initialize the verification for a period
+#[utoipa::path(
+    post,
+    path = "/init",
+    tag = "workflow",
+    request_body = InitRequest,
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
 pub async fn init_handler(
-    State(state): State<AppDataLockArc>,
-    Json(payload): Json<InitRequest>,
-) -> Json<StatusResponse> {
+    Extension(state): Extension<AppDataLockArc>,
+    AppJson(payload): AppJson<InitRequest>,
+) -> Result<Json<StatusResponse>, AppError> {
     let mut state_mut = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::Init)?;
     state_mut.verfification_period = Some(VerificationPeriod::from(&payload.period));
     info!("Verification period set to {}", payload.period.as_ref());
-    update_status(&mut state_mut, AppStatus::Initialized);
-    get_status_response(&state_mut)
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
 }
 
-pub async fn reset_handler(State(state): State<AppDataLockArc>) -> Json<StatusResponse> {
+/// Cancel the extraction or the run in progress
+///
+/// The verifications not started or running are marked as cancelled. The extraction or the
+/// run is blocking and cannot be stopped: it keeps working in the background until its end,
+/// and its results are discarded. Until then, no extraction or run can be started
+#[utoipa::path(
+    post,
+    path = "/cancel",
+    tag = "workflow",
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse)
+    )
+)]
+pub async fn cancel_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Json<StatusResponse>, AppError> {
     let mut state_mut = state.write().await;
-    *state_mut = AppData::default();
-    info!("Application reseted");
-    get_status_response(&state_mut)
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::Cancel)?;
+    state_mut.cancel();
+    info!("{} cancelled", state_mut.app_status.as_ref());
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
 }
 
-pub async fn manual_checks_handler() {
-    todo!()
+/// Reset the application to the status `NotInitialized`
+#[utoipa::path(
+    post,
+    path = "/reset",
+    tag = "workflow",
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse)
+    )
+)]
+pub async fn reset_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let mut state_mut = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::Reset)?;
+    state_mut.reset();
+    info!("Application reseted");
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
 }
@@ -0,0 +1,178 @@
+use crate::{
+    app_data::AppStatus,
+    router::{RoutePath, ALLOWED_ROUTE_PATHES},
+};
+use serde_json::json;
+use std::str::FromStr;
+use utoipa::{
+    openapi::{
+        extensions::ExtensionsBuilder,
+        security::{HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme},
+        OpenApi as OpenApiDoc,
+    },
+    Modify, OpenApi,
+};
+
+/// Name of the security scheme of the bearer token, required by all the operations
+/// (except the ones with an empty requirement)
+const SECURITY_SCHEME_NAME: &str = "token";
+
+/// Name of the extension of the operations containing the status where the route is allowed
+pub const ALLOWED_STATUS_EXTENSION: &str = "x-allowed-status";
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                SECURITY_SCHEME_NAME,
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            )
+        }
+        openapi.security = Some(vec![SecurityRequirement::new(
+            SECURITY_SCHEME_NAME,
+            Vec::<String>::new(),
+        )]);
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Verifier GUI Backend",
+        description = "Backend of the GUI of the E-Voting Verifier.\n\n\
+            The requests, except the health check, need the bearer token written at each start \
+            of the server in the file `data/api_token`.\n\n\
+            The routes of a verification are also available in a session, under \
+            `/sessions/{session_id}`. Each route is only allowed in some status of the \
+            application (see `x-allowed-status`), else the error 409 is returned."
+    ),
+    paths(
+        super::health_check_handler,
+        super::status_handler,
+        super::events::events_handler,
+        super::state_machine::state_machine_handler,
+        super::metrics::metrics_handler,
+        super::verifications::verifications_handler,
+        super::manual_checks::manual_checks_handler,
+        super::report::report_handler,
+        super::init_handler,
+        super::files::files_handler,
+        super::send_file::context_dataset_handler,
+        super::send_file::context_dataset_upload_handler,
+        super::send_file::period_dataset_handler,
+        super::send_file::period_dataset_upload_handler,
+        super::extract::extract_handler,
+        super::run::run_handler,
+        super::run::run_failed_handler,
+        super::run::run_verification_handler,
+        super::cancel_handler,
+        super::retry::retry_handler,
+        super::retry::back_handler,
+        super::reset_handler,
+        super::session::create_session_handler,
+        super::session::list_sessions_handler,
+        super::session::delete_session_handler,
+    ),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "status", description = "Status and monitoring of the application"),
+        (name = "workflow", description = "Steps of the verification"),
+        (name = "results", description = "Results of the verification"),
+        (name = "sessions", description = "Concurrent verifications"),
+    )
+)]
+struct ApiDoc;
+
+/// Status where the route is allowed
+fn allowed_status(route: RoutePath) -> Vec<AppStatus> {
+    ALLOWED_ROUTE_PATHES
+        .iter()
+        .filter(|(_, routes)| routes.contains(&route))
+        .map(|(status, _)| *status)
+        .collect()
+}
+
+/// OpenAPI document of the API
+///
+/// The status where each route is allowed (see [ALLOWED_ROUTE_PATHES]) are added to the
+/// description and in the extension [ALLOWED_STATUS_EXTENSION] of the operations
+pub fn api_doc() -> OpenApiDoc {
+    let mut doc = ApiDoc::openapi();
+    doc.info.version = env!("CARGO_PKG_VERSION").to_string();
+    for (path, item) in doc.paths.paths.iter_mut() {
+        // The parameters are written `{param}` in OpenAPI and `:param` in axum
+        let axum_path = path.replace('{', ":").replace('}', "");
+        let Ok(route) = RoutePath::from_str(&axum_path) else {
+            continue;
+        };
+        let status = allowed_status(route);
+        let names = status.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+        for operation in [&mut item.get, &mut item.post, &mut item.delete]
+            .into_iter()
+            .flatten()
+        {
+            let allowed = format!("Allowed in the status: {}", names.join(", "));
+            operation.description = Some(match operation.description.take() {
+                Some(d) => format!("{}\n\n{}", d, allowed),
+                None => allowed,
+            });
+            let extensions = ExtensionsBuilder::new()
+                .add(ALLOWED_STATUS_EXTENSION, json!(names))
+                .build();
+            match operation.extensions.as_mut() {
+                Some(e) => e.merge(extensions),
+                None => operation.extensions = Some(extensions),
+            }
+        }
+    }
+    doc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_all_routes_documented() {
+        let doc = api_doc();
+        for route in RoutePath::iter() {
+            let path = route
+                .as_ref()
+                .split('/')
+                .map(|s| match s.strip_prefix(':') {
+                    Some(p) => format!("{{{}}}", p),
+                    None => s.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            assert!(
+                doc.paths.paths.contains_key(&path),
+                "{} not documented",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_allowed_status() {
+        let doc = serde_json::to_value(api_doc()).unwrap();
+        let init = &doc["paths"]["/init"]["post"];
+        assert_eq!(init[ALLOWED_STATUS_EXTENSION], json!(["NotInitialized"]));
+        assert!(init["description"]
+            .as_str()
+            .unwrap()
+            .ends_with("Allowed in the status: NotInitialized"));
+        let run = &doc["paths"]["/run/{verification_id}"]["post"];
+        assert_eq!(run[ALLOWED_STATUS_EXTENSION], json!(["Finished"]));
+        assert!(doc["paths"]["/sessions"]["post"]
+            .get(ALLOWED_STATUS_EXTENSION)
+            .is_none());
+        assert!(doc["components"]["schemas"]["StatusResponse"].is_object());
+        assert!(doc["components"]["schemas"]["ErrorResponse"].is_object());
+        assert!(doc["components"]["securitySchemes"][SECURITY_SCHEME_NAME].is_object());
+        assert_eq!(doc["paths"]["/"]["get"]["security"], json!([{}]));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{
+    app_data::AppDataLockArc,
+    error::{AppError, ErrorCode, ErrorResponse, ResultExt},
+    report::{ReportData, ReportFormat},
+    request::{AppQuery, ReportRequest},
+};
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use tracing::info;
+
+/// Report of the verifications, in HTML (default) or PDF format
+#[utoipa::path(
+    get,
+    path = "/report",
+    tag = "results",
+    params(ReportRequest),
+    responses(
+        (status = 200, description = "Report in HTML or PDF", content(
+            (String = "text/html"),
+            (Vec<u8> = "application/pdf")
+        )),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn report_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    AppQuery(payload): AppQuery<ReportRequest>,
+) -> Result<Response, AppError> {
+    let data = ReportData::from(&*state.read().await);
+    let format = payload.format;
+    let content = tokio::task::spawn_blocking(move || data.render(format))
+        .await
+        .app_err(ErrorCode::InternalError, "Error in the report task")?
+        .app_err(
+            ErrorCode::InternalError,
+            &format!("Error generating the {} report", format.as_ref()),
+        )?;
+    info!("Report generated in format {}", format.as_ref());
+    let (content_type, file_name) = match format {
+        ReportFormat::Html => (mime::TEXT_HTML_UTF_8.as_ref(), "report.html"),
+        ReportFormat::Pdf => (mime::APPLICATION_PDF.as_ref(), "report.pdf"),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", file_name),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
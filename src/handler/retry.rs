@@ -0,0 +1,83 @@
+use super::{extract::start_extraction, get_status_response, run::start_run, set_status};
+use crate::{
+    app_data::{AppData, AppDataLockArc, AppStatus},
+    error::{AppError, ErrorCode, ErrorResponse},
+    request::{AppJsonOrDefault, RetryRequest},
+    response::StatusResponse,
+    state_machine::{checked_next_status, TransitionEvent},
+};
+use axum::{Extension, Json};
+use tracing::info;
+
+/// Retry the failed extraction or run with the same inputs
+#[utoipa::path(
+    post,
+    path = "/retry",
+    tag = "workflow",
+    request_body(content = Option<RetryRequest>, description = "Password of the datasets, only for the extraction"),
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status, or cancelled job still working", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn retry_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    AppJsonOrDefault(payload): AppJsonOrDefault<RetryRequest>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    let from = state_mut.app_status;
+    let status = checked_next_status(from, TransitionEvent::Retry)?;
+    match status {
+        AppStatus::Extracting => {
+            // The password is not persisted, and must be given after a restart of the backend
+            let password = payload
+                .password
+                .or_else(|| state_mut.dataset_password.clone())
+                .ok_or_else(|| {
+                    AppError::new(
+                        ErrorCode::InvalidInput,
+                        "The password of the datasets is missing",
+                    )
+                })?;
+            start_extraction(&state, &mut state_mut, password)?;
+        }
+        AppStatus::Running => {
+            let exclusions = state_mut.exclusions.clone();
+            let strategy = state_mut.run_strategy.unwrap_or_default();
+            start_run(&state, &mut state_mut, exclusions, strategy)?;
+        }
+        _ => {
+            return Err(AppError::new(
+                ErrorCode::InternalError,
+                &format!("Retry to {} not implemented", status.as_ref()),
+            ))
+        }
+    }
+    state_mut.error = None;
+    state_mut.error_code = None;
+    info!("Retry after {}", from.as_ref());
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
+}
+
+/// Go back to the previous step. The data set after this step are cleared
+#[utoipa::path(
+    post,
+    path = "/back",
+    tag = "workflow",
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse)
+    )
+)]
+pub async fn back_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let mut state_mut = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::Back)?;
+    state_mut.clear_after(status);
+    info!("Back from {}", state_mut.app_status.as_ref());
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
+}
@@ -1,101 +1,468 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
-use super::{get_status_response, update_status, update_with_error};
+use super::{
+    check_no_previous_job, get_status_response, set_status, update_status, update_with_error,
+};
 use crate::{
-    app_data::{AppData, AppDataLockArc, AppStatus, VerificationStatusEnum},
+    app_data::{
+        AppData, AppDataLockArc, AppStatus, BackgroundJobGuard, RunStrategyDef,
+        VerificationStatusEnum,
+    },
+    error::{AppError, ErrorCode, ErrorResponse, ResultExt},
+    metrics::METRICS,
+    request::{AppJsonOrDefault, RunRequest},
     response::StatusResponse,
-    AppError,
+    state_machine::{checked_next_status, TransitionEvent},
 };
-use axum::{extract::State, Json};
+use axum::{extract::Path, Extension, Json};
+use chrono::{DateTime, Local};
 use rust_ev_verifier_lib::{
     application_runner::{RunParallel, Runner},
     verification::{VerificationMetaDataList, VerificationPeriod},
     Config,
 };
+use tokio::sync::mpsc::{self, UnboundedSender};
 use tracing::{debug, info, instrument, trace};
 
-#[instrument(skip(state, config, metada_list))]
-async fn run_fn(
-    state: AppDataLockArc,
+/// Name of the path parameter containing the id of the verification
+pub const VERIFICATION_ID_PARAM: &str = "verification_id";
+
+fn finish_run(state_mut: &mut AppData) {
+    state_mut.run_finished_at = Some(Local::now());
+    if update_status(state_mut, TransitionEvent::RunSucceeded).is_ok() {
+        METRICS.observe_run(state_mut);
+    }
+}
+
+/// Progress of the run, sent by the callbacks of the runner to the task updating the state
+///
+/// The time is taken in the callbacks, since the task can apply the progress later
+#[derive(Debug)]
+enum RunProgress {
+    Started {
+        id: String,
+        at: DateTime<Local>,
+    },
+    Finished {
+        id: String,
+        errors: Vec<String>,
+        failures: Vec<String>,
+        at: DateTime<Local>,
+    },
+}
+
+/// Run the verifications in the current (blocking) thread
+///
+/// The progress is sent through the channel. The sending errors are ignored, since the
+/// receiver is dropped if the run is cancelled
+fn run_blocking(
     period: VerificationPeriod,
     extracted_location: PathBuf,
     metada_list: &VerificationMetaDataList,
+    exclusions: Vec<String>,
+    strategy: RunStrategyDef,
     config: &'static Config,
-) {
-    let state_before = state.clone();
-    let state_after = state.clone();
-    let mut runner = match Runner::new(
+    progress: UnboundedSender<RunProgress>,
+) -> Result<(), (ErrorCode, String)> {
+    let exclusions_str = exclusions.iter().map(String::as_str).collect::<Vec<_>>();
+    let progress_after = progress.clone();
+    let mut runner = Runner::new(
         extracted_location.as_path(),
         &period,
-        &metada_list,
-        &[],
+        metada_list,
+        &exclusions_str,
         RunParallel,
         config,
         move |id| {
             trace!("before for {}", id);
-            let mut data_mut = futures::executor::block_on(state_before.write());
-            data_mut.verification_status.get_mut(id).unwrap().status =
-                VerificationStatusEnum::Running;
-            trace!("end of before for {}", id);
+            let _ = progress.send(RunProgress::Started {
+                id: id.to_string(),
+                at: Local::now(),
+            });
         },
         move |id, errors, failures| {
             trace!("after for {}", id);
-            let mut data_mut = futures::executor::block_on(state_after.write());
-            data_mut.set_verification_status(id, errors, failures);
-            if !data_mut.not_finished() {
-                update_status(&mut data_mut, AppStatus::Finished);
-            }
-            trace!("end of after for {}", id);
+            let _ = progress_after.send(RunProgress::Finished {
+                id: id.to_string(),
+                errors,
+                failures,
+                at: Local::now(),
+            });
         },
-    ) {
-        Ok(res) => res,
-        Err(e) => {
-            let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
-            update_with_error(
-                &mut state_mut,
-                AppStatus::RunError,
-                format!("Error creating the runner: {:?}", e).as_str(),
-            );
-            return;
-        }
-    };
+    )
+    .map_err(|e| {
+        (
+            ErrorCode::RunFailed,
+            format!("Error creating the runner: {:?}", e),
+        )
+    })?;
     debug!("Runner created");
-    if let Err(e) = runner.run_all(&metada_list) {
+    // The sequential strategy of the library is not available. A thread pool with one thread
+    // has the same effect on the parallel strategy
+    match strategy {
+        RunStrategyDef::Parallel => runner.run_all(metada_list),
+        RunStrategyDef::Sequential => rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .map_err(|e| {
+                (
+                    ErrorCode::InternalError,
+                    format!("Error creating the thread pool: {:?}", e),
+                )
+            })?
+            .install(|| runner.run_all(metada_list)),
+    }
+    .map_err(|e| {
+        (
+            ErrorCode::RunFailed,
+            format!("error running the tests: {:?}", e),
+        )
+    })
+}
+
+fn apply_progress(state_mut: &mut AppData, progress: RunProgress) {
+    // The progress is ignored if the run has been cancelled
+    if state_mut.app_status != AppStatus::Running {
+        return;
+    }
+    match progress {
+        RunProgress::Started { id, at } => state_mut.set_verification_running(&id, at),
+        RunProgress::Finished {
+            id,
+            errors,
+            failures,
+            at,
+        } => {
+            if state_mut.verification_status.get(&id).map(|v| v.status)
+                == Some(VerificationStatusEnum::Running)
+            {
+                state_mut.set_verification_status(&id, errors, failures, at);
+            }
+            if !state_mut.not_finished() {
+                finish_run(state_mut);
+            }
+        }
+    }
+}
+
+/// Run the verifications in a blocking thread, and update the state with the progress
+///
+/// If the task is aborted (cancel), the blocking thread cannot be stopped and runs until
+/// the end, but its progress and its result are ignored. The job is counted until this end
+#[instrument(skip(state, config, metada_list, job))]
+#[allow(clippy::too_many_arguments)]
+async fn run_fn(
+    state: AppDataLockArc,
+    period: VerificationPeriod,
+    extracted_location: PathBuf,
+    metada_list: VerificationMetaDataList,
+    exclusions: Vec<String>,
+    strategy: RunStrategyDef,
+    config: &'static Config,
+    job: BackgroundJobGuard,
+) {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let handle = tokio::task::spawn_blocking(move || {
+        let _job = job;
+        run_blocking(
+            period,
+            extracted_location,
+            &metada_list,
+            exclusions,
+            strategy,
+            config,
+            sender,
+        )
+    });
+    // The channel is closed when the runner (with the senders) is dropped at the end of the run
+    while let Some(progress) = receiver.recv().await {
         let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
-        update_with_error(
-            &mut state_mut,
-            AppStatus::RunError,
-            format!("error running the tests: {:?}", e).as_str(),
-        );
+        apply_progress(&mut state_mut, progress);
+    }
+    let run_result = handle.await.unwrap_or_else(|e| {
+        Err((
+            ErrorCode::InternalError,
+            format!("Error in the thread of the run: {:?}", e),
+        ))
+    });
+    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    if state_mut.app_status != AppStatus::Running {
+        if let Err((_, e)) = run_result {
+            info!("Run cancelled. Error ignored: {}", e);
+        }
+        return;
+    }
+    match run_result {
+        Ok(_) => {
+            // Necessary if all the verifications are excluded
+            if !state_mut.not_finished() {
+                finish_run(&mut state_mut);
+            }
+        }
+        Err((code, e)) => {
+            if update_with_error(&mut state_mut, TransitionEvent::RunFailed, code, &e).is_ok() {
+                METRICS.observe_run(&state_mut);
+            }
+        }
     }
 }
 
+/// Spawn the run of the verifications. The verifications in `exclusions` are not run
+fn spawn_run(
+    state: &AppDataLockArc,
+    state_mut: &mut AppData,
+    metadata: VerificationMetaDataList,
+    exclusions: Vec<String>,
+    strategy: RunStrategyDef,
+) {
+    let status_spawn = state.clone();
+    let period = state_mut.verfification_period.unwrap();
+    let extracted_location = state_mut.extracted_location.clone().unwrap();
+    let config = state_mut.config;
+    let job = state_mut.background_jobs.start();
+    let handle = tokio::spawn(async move {
+        run_fn(
+            status_spawn,
+            period,
+            extracted_location,
+            metadata,
+            exclusions,
+            strategy,
+            config,
+            job,
+        )
+        .await
+    });
+    state_mut.task_handle = Some(handle.abort_handle());
+    state_mut.run_started_at = Some(Local::now());
+    state_mut.run_finished_at = None;
+}
+
+fn load_metadata(state_mut: &AppData) -> Result<VerificationMetaDataList, AppError> {
+    VerificationMetaDataList::load(state_mut.config.get_verification_list_str()).app_err(
+        ErrorCode::MissingConfig,
+        "Error loading the list of verifications",
+    )
+}
+
+/// Validate the exclusions and spawn the run of the verifications
+///
+/// The status is not changed. Error if a cancelled extraction or run is still working
+pub(super) fn start_run(
+    state: &AppDataLockArc,
+    state_mut: &mut AppData,
+    exclusions: Vec<String>,
+    strategy: RunStrategyDef,
+) -> Result<(), AppError> {
+    check_no_previous_job(state_mut)?;
+    let metadata = load_metadata(state_mut)?;
+
+    let period_ids = metadata.id_list_for_period(state_mut.verfification_period.as_ref().unwrap());
+    let unknown_ids = exclusions
+        .iter()
+        .filter(|&id| !period_ids.contains(&id.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !unknown_ids.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            &format!(
+                "Verifications to exclude not found for the period: {}",
+                unknown_ids.join(", ")
+            ),
+        ));
+    }
+    if !exclusions.is_empty() {
+        info!("Verifications excluded: {}", exclusions.join(", "));
+    }
+    state_mut.set_with_medata(&metadata, &exclusions);
+
+    state_mut.run_strategy = Some(strategy);
+    info!(
+        "Start the verification for period {} ({})",
+        state_mut.verfification_period.as_ref().unwrap().as_ref(),
+        strategy.as_ref()
+    );
+    spawn_run(state, state_mut, metadata, exclusions, strategy);
+    Ok(())
+}
+
+/// Start the run of the verifications
+#[utoipa::path(
+    post,
+    path = "/run",
+    tag = "workflow",
+    request_body(content = Option<RunRequest>, description = "Exclusions and strategy. Optional: an empty body runs all the verifications in parallel"),
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status, or cancelled job still working", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
 pub async fn run_handler(
-    State(state): State<AppDataLockArc>,
+    Extension(state): Extension<AppDataLockArc>,
+    AppJsonOrDefault(payload): AppJsonOrDefault<RunRequest>,
 ) -> Result<Json<StatusResponse>, AppError> {
     let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::StartRun)?;
+    start_run(&state, &mut state_mut, payload.exclusions, payload.strategy)?;
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
+}
 
-    let metadata =
-        VerificationMetaDataList::load(state_mut.config.get_verification_list_str()).unwrap();
-
-    state_mut.set_with_medata(&metadata);
-
+/// Run again the verifications with the ids, with the strategy of the last run
+///
+/// The other verifications keep their results
+fn start_rerun(
+    state: &AppDataLockArc,
+    state_mut: &mut AppData,
+    ids: Vec<String>,
+) -> Result<(), AppError> {
+    check_no_previous_job(state_mut)?;
+    let metadata = load_metadata(state_mut)?;
+    let runner_exclusions = metadata
+        .id_list_for_period(state_mut.verfification_period.as_ref().unwrap())
+        .into_iter()
+        .filter(|&id| !ids.iter().any(|i| i == id))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let strategy = state_mut.run_strategy.unwrap_or_default();
     info!(
-        "Start the verification for period {}",
-        state_mut.verfification_period.as_ref().unwrap().as_ref()
+        "Run again the verifications {} ({})",
+        ids.join(", "),
+        strategy.as_ref()
     );
-    let status_spawn = state.clone();
-    let period = state_mut.verfification_period.unwrap().clone();
-    let extracted_location = state_mut
-        .extracted_dataset_result
-        .as_ref()
-        .unwrap()
-        .location()
-        .to_path_buf();
-    let config = state_mut.config;
-    tokio::spawn(async move {
-        run_fn(status_spawn, period, extracted_location, &metadata, config).await
-    });
-    update_status(&mut state_mut, AppStatus::Running);
+    state_mut.reset_verifications(&ids);
+    spawn_run(state, state_mut, metadata, runner_exclusions, strategy);
+    Ok(())
+}
+
+/// Run again the verifications finished with failures or errors
+#[utoipa::path(
+    post,
+    path = "/run/failed",
+    tag = "workflow",
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status, or cancelled job still working", body = ErrorResponse)
+    )
+)]
+pub async fn run_failed_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::Rerun)?;
+    let ids = state_mut.failed_verifications();
+    if ids.is_empty() {
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "No verification finished with failures or errors",
+        ));
+    }
+    start_rerun(&state, &mut state_mut, ids)?;
+    set_status(&mut state_mut, status);
+    Ok(get_status_response(&state_mut))
+}
+
+/// Run again the verification given in the path
+#[utoipa::path(
+    post,
+    path = "/run/{verification_id}",
+    tag = "workflow",
+    params(("verification_id" = String, Path, description = "Id of the verification")),
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 404, description = "Verification not found for the period", body = ErrorResponse),
+        (status = 409, description = "Route not allowed in the actual status, or cancelled job still working", body = ErrorResponse)
+    )
+)]
+pub async fn run_verification_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let id = params
+        .get(VERIFICATION_ID_PARAM)
+        .cloned()
+        .unwrap_or_default();
+    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::Rerun)?;
+    match state_mut.verification_status.get(&id).map(|v| v.status) {
+        None => {
+            return Err(AppError::new(
+                ErrorCode::VerificationNotFound,
+                &format!("Verification {} not found", id),
+            ))
+        }
+        Some(VerificationStatusEnum::Excluded) => {
+            return Err(AppError::new(
+                ErrorCode::InvalidInput,
+                &format!("Verification {} is excluded", id),
+            ))
+        }
+        Some(_) => (),
+    }
+    start_rerun(&state, &mut state_mut, vec![id])?;
+    set_status(&mut state_mut, status);
     Ok(get_status_response(&state_mut))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CONFIG;
+
+    #[test]
+    fn test_apply_progress() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut data = AppData::default();
+        data.verfification_period = Some(VerificationPeriod::Setup);
+        let ids = metadata
+            .id_list_for_period(&VerificationPeriod::Setup)
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        data.set_with_medata(&metadata, &ids[1..]);
+
+        // Ignored if not running
+        let started_at = Local::now() - chrono::TimeDelta::seconds(10);
+        let finished_at = started_at + chrono::TimeDelta::seconds(5);
+        apply_progress(
+            &mut data,
+            RunProgress::Started {
+                id: ids[0].clone(),
+                at: started_at,
+            },
+        );
+        assert_eq!(
+            data.verification_status.get(&ids[0]).unwrap().status,
+            VerificationStatusEnum::NotStarted
+        );
+
+        data.app_status = AppStatus::Running;
+        apply_progress(
+            &mut data,
+            RunProgress::Started {
+                id: ids[0].clone(),
+                at: started_at,
+            },
+        );
+        let status = data.verification_status.get(&ids[0]).unwrap();
+        assert_eq!(status.status, VerificationStatusEnum::Running);
+        // The time of the runner is used, not the time of the update of the state
+        assert_eq!(status.started_at, Some(started_at));
+        apply_progress(
+            &mut data,
+            RunProgress::Finished {
+                id: ids[0].clone(),
+                errors: vec![],
+                failures: vec!["failure".to_string()],
+                at: finished_at,
+            },
+        );
+        let status = data.verification_status.get(&ids[0]).unwrap();
+        assert_eq!(status.status, VerificationStatusEnum::FinishedWithFailures);
+        assert_eq!(status.finished_at, Some(finished_at));
+        assert_eq!(data.app_status, AppStatus::Finished);
+        assert!(data.run_finished_at.is_some());
+    }
+}
@@ -1,60 +1,315 @@
-use super::{get_status_response, update_status};
+use super::{get_status_response, set_status};
 use crate::{
-    app_data::{AppData, AppDataLockArc, AppStatus},
-    request::FilePathRequest,
+    app_data::{AppData, AppDataLockArc},
+    error::{AppError, ErrorCode, ErrorResponse, ResultExt},
+    files::{canonical_dataset_path, DATASET_ROOTS},
+    fingerprint::sha256_file_async,
+    request::{AppJson, FilePathRequest, UploadRequest, UPLOAD_FIELD_NAME},
     response::StatusResponse,
-    AppError,
+    state_machine::{checked_next_status, TransitionEvent},
 };
 use anyhow::anyhow;
-use axum::{extract::State, Json};
+use axum::{extract::Multipart, Extension, Json};
 use rust_ev_verifier_lib::verification::VerificationPeriod;
-use tracing::{error, info};
+use std::path::{Path, PathBuf};
+use tokio::{fs::File, io::AsyncWriteExt};
+use tracing::info;
+use uuid::Uuid;
 
-pub async fn context_dataset_handler(
-    State(state): State<AppDataLockArc>,
-    Json(payload): Json<FilePathRequest>,
-) -> Result<Json<StatusResponse>, AppError> {
-    if !payload.path.exists() {
-        let msg = format!("Context file {} not exist", payload.path.to_str().unwrap());
-        error!(msg);
-        return Err(AppError::from(anyhow!(msg)));
-    }
-    let mut state_mut = state.write().await;
-    state_mut.input_file_location.context_zip_file = Some(payload.path.clone());
+/// Maximal size of an uploaded dataset (1 GiB)
+pub const MAX_UPLOAD_SIZE: usize = 1024 * 1024 * 1024;
+
+/// SHA-256 of the dataset
+///
+/// The whole file is read. It must be calculated before taking the lock of the state
+async fn dataset_sha256(path: &Path) -> Result<String, AppError> {
+    sha256_file_async(path).await.app_err(
+        ErrorCode::InvalidInput,
+        &format!("Error calculating the SHA-256 of {}", path.display()),
+    )
+}
+
+fn set_context_dataset(
+    state_mut: &mut AppData,
+    path: &Path,
+    sha256: String,
+) -> Result<(), AppError> {
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::LoadContextDataset)?;
+    state_mut.input_file_location.context_zip_file = Some(path.to_path_buf());
     info!(
-        "Context input dataset set to {}",
-        payload.path.to_str().unwrap()
+        "Context input dataset set to {} (SHA-256 {})",
+        path.to_str().unwrap(),
+        sha256
     );
-    update_status(&mut state_mut, AppStatus::ContextDataSetLoaded);
-    Ok(get_status_response(&state_mut))
+    state_mut.input_file_location.context_sha256 = Some(sha256);
+    set_status(state_mut, status);
+    Ok(())
 }
 
-pub async fn period_dataset_handler(
-    State(state): State<AppDataLockArc>,
-    Json(payload): Json<FilePathRequest>,
-) -> Result<Json<StatusResponse>, AppError> {
-    if !payload.path.exists() {
-        let msg = format!(
-            "Period dataset file {} not exist",
-            payload.path.to_str().unwrap()
-        );
-        error!(msg);
-        return Err(AppError::from(anyhow!(msg)));
-    }
-    let mut state_mut: tokio::sync::RwLockWriteGuard<'_, AppData> = state.write().await;
+fn set_period_dataset(
+    state_mut: &mut AppData,
+    path: &Path,
+    sha256: String,
+) -> Result<(), AppError> {
+    let status = checked_next_status(state_mut.app_status, TransitionEvent::LoadPeriodDataset)?;
+    info!(
+        "input dataset for {} set to {} (SHA-256 {})",
+        state_mut.verfification_period.unwrap().as_ref(),
+        path.to_str().unwrap(),
+        sha256
+    );
+    let location = &mut state_mut.input_file_location;
     match state_mut.verfification_period.unwrap() {
         VerificationPeriod::Setup => {
-            state_mut.input_file_location.setup_zip_file = Some(payload.path.clone())
+            location.setup_zip_file = Some(path.to_path_buf());
+            location.setup_sha256 = Some(sha256);
         }
         VerificationPeriod::Tally => {
-            state_mut.input_file_location.tally_zip_file = Some(payload.path.clone())
+            location.tally_zip_file = Some(path.to_path_buf());
+            location.tally_sha256 = Some(sha256);
         }
     }
-    info!(
-        "input dataset for {} set to {}",
-        state_mut.verfification_period.unwrap().as_ref(),
-        payload.path.to_str().unwrap()
-    );
-    update_status(&mut state_mut, AppStatus::PeriodDataSetLoaded);
+    set_status(state_mut, status);
+    Ok(())
+}
+
+/// Set the dataset with its canonical path in the state with `set`
+async fn set_dataset_path(
+    state: &AppDataLockArc,
+    path: &Path,
+    set: fn(&mut AppData, &Path, String) -> Result<(), AppError>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let sha256 = dataset_sha256(path).await?;
+    let mut state_mut = state.write().await;
+    set(&mut state_mut, path, sha256)?;
     Ok(get_status_response(&state_mut))
 }
+
+/// Set the context dataset given on the command line
+///
+/// The path is not checked against the dataset directories, only its existence
+pub async fn context_dataset_from_cli(
+    state: &AppDataLockArc,
+    path: &Path,
+) -> Result<Json<StatusResponse>, AppError> {
+    let path = canonical_dataset_path(path)?;
+    set_dataset_path(state, &path, set_context_dataset).await
+}
+
+/// Set the dataset of the period given on the command line
+///
+/// The path is not checked against the dataset directories, only its existence
+pub async fn period_dataset_from_cli(
+    state: &AppDataLockArc,
+    path: &Path,
+) -> Result<Json<StatusResponse>, AppError> {
+    let path = canonical_dataset_path(path)?;
+    set_dataset_path(state, &path, set_period_dataset).await
+}
+
+/// Stream the uploaded dataset in the upload directory and return the path of the stored file
+///
+/// The file is stored with the prefix `kind`, to avoid that the datasets overwrite each other
+async fn store_uploaded_dataset(
+    upload_dir: &Path,
+    kind: &str,
+    mut multipart: Multipart,
+) -> anyhow::Result<PathBuf> {
+    while let Some(mut field) = multipart.next_field().await? {
+        if field.name() != Some(UPLOAD_FIELD_NAME) {
+            continue;
+        }
+        // Only the file name is taken, to avoid writing outside the upload directory
+        let file_name = field
+            .file_name()
+            .and_then(|n| Path::new(n).file_name())
+            .and_then(|n| n.to_str())
+            .ok_or(anyhow!("The uploaded file has no valid file name"))?
+            .to_string();
+        tokio::fs::create_dir_all(upload_dir).await?;
+        let path = upload_dir.join(format!("{}-{}", kind, file_name));
+        let mut file = File::create(&path).await?;
+        let mut size = 0;
+        while let Some(chunk) = field.chunk().await? {
+            size += chunk.len();
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        info!(
+            "Dataset {} uploaded ({} bytes) in {}",
+            file_name,
+            size,
+            path.to_str().unwrap()
+        );
+        return Ok(path);
+    }
+    Err(anyhow!(
+        "No field {} found in the multipart request",
+        UPLOAD_FIELD_NAME
+    ))
+}
+
+/// Store the uploaded dataset in its own directory. The directory is deleted if the upload fails
+async fn upload_dataset(
+    state: &AppDataLockArc,
+    kind: &str,
+    multipart: Multipart,
+) -> Result<PathBuf, AppError> {
+    // Each upload has its own directory, so that the sessions don't overwrite their datasets
+    let upload_dir = state
+        .read()
+        .await
+        .upload_dir()
+        .join(Uuid::new_v4().to_string());
+    let res = store_uploaded_dataset(&upload_dir, kind, multipart)
+        .await
+        .app_err(
+            ErrorCode::UploadFailed,
+            &format!("Error uploading the {} dataset", kind),
+        );
+    if res.is_err() {
+        let _ = tokio::fs::remove_dir_all(&upload_dir).await;
+    }
+    res
+}
+
+/// Set the uploaded dataset in the state with `set`
+///
+/// The uploaded dataset is deleted if it cannot be set (e.g. status changed during the upload)
+async fn set_uploaded_dataset(
+    state: &AppDataLockArc,
+    path: &Path,
+    set: fn(&mut AppData, &Path, String) -> Result<(), AppError>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let res = match dataset_sha256(path).await {
+        Ok(sha256) => {
+            let mut state_mut = state.write().await;
+            set(&mut state_mut, path, sha256).map(|_| get_status_response(&state_mut))
+        }
+        Err(e) => Err(e),
+    };
+    if res.is_err() {
+        state.read().await.remove_upload(path);
+    }
+    res
+}
+
+/// Set the context dataset with a path on the machine of the backend
+///
+/// The path must be in the dataset directories (see `/files`)
+#[utoipa::path(
+    post,
+    path = "/context-dataset",
+    tag = "workflow",
+    request_body = FilePathRequest,
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 403, description = "File not in the dataset directories", body = ErrorResponse),
+        (status = 404, description = "File not found", body = ErrorResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn context_dataset_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    AppJson(payload): AppJson<FilePathRequest>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let path = DATASET_ROOTS.resolve(&payload.path)?;
+    set_dataset_path(&state, &path, set_context_dataset).await
+}
+
+/// Upload the context dataset
+#[utoipa::path(
+    post,
+    path = "/context-dataset/upload",
+    tag = "workflow",
+    request_body(content = UploadRequest, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Upload failed", body = ErrorResponse)
+    )
+)]
+pub async fn context_dataset_upload_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    multipart: Multipart,
+) -> Result<Json<StatusResponse>, AppError> {
+    let path = upload_dataset(&state, "context", multipart).await?;
+    set_uploaded_dataset(&state, &path, set_context_dataset).await
+}
+
+/// Set the dataset of the period with a path on the machine of the backend
+///
+/// The path must be in the dataset directories (see `/files`)
+#[utoipa::path(
+    post,
+    path = "/period-dataset",
+    tag = "workflow",
+    request_body = FilePathRequest,
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 403, description = "File not in the dataset directories", body = ErrorResponse),
+        (status = 404, description = "File not found", body = ErrorResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn period_dataset_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    AppJson(payload): AppJson<FilePathRequest>,
+) -> Result<Json<StatusResponse>, AppError> {
+    let path = DATASET_ROOTS.resolve(&payload.path)?;
+    set_dataset_path(&state, &path, set_period_dataset).await
+}
+
+/// Upload the dataset of the period
+#[utoipa::path(
+    post,
+    path = "/period-dataset/upload",
+    tag = "workflow",
+    request_body(content = UploadRequest, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Status after the request", body = StatusResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse),
+        (status = 422, description = "Upload failed", body = ErrorResponse)
+    )
+)]
+pub async fn period_dataset_upload_handler(
+    Extension(state): Extension<AppDataLockArc>,
+    multipart: Multipart,
+) -> Result<Json<StatusResponse>, AppError> {
+    // The status middleware has released the lock: a concurrent /back or /reset can have
+    // removed the period
+    let kind = state.read().await.verfification_period.ok_or_else(|| {
+        AppError::new(
+            ErrorCode::InvalidStateTransition,
+            "The period of the verification is not set",
+        )
+    })?;
+    let path = upload_dataset(&state, kind.as_ref(), multipart).await?;
+    set_uploaded_dataset(&state, &path, set_period_dataset).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app_data::AppData;
+    use axum::{body::Body, extract::FromRequest, http::Request};
+
+    #[tokio::test]
+    async fn test_upload_without_period() {
+        // The period can be removed by a concurrent /reset after the status middleware
+        let request = Request::builder()
+            .header(
+                axum::http::header::CONTENT_TYPE,
+                "multipart/form-data; boundary=test",
+            )
+            .body(Body::from("--test--\r\n"))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+        match period_dataset_upload_handler(Extension(AppData::new()), multipart).await {
+            Err(e) => assert_eq!(e.code(), ErrorCode::InvalidStateTransition),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    error::{AppError, ErrorCode, ErrorResponse},
+    response::SessionResponse,
+    session::Sessions,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Create a new session
+///
+/// The routes of the session are available under `/sessions/<session_id>`
+#[utoipa::path(
+    post,
+    path = "/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Session created", body = SessionResponse)
+    )
+)]
+pub async fn create_session_handler(State(sessions): State<Sessions>) -> Json<SessionResponse> {
+    Json(SessionResponse {
+        session_id: sessions.create().await,
+    })
+}
+
+/// Ids of the sessions
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Ids of the sessions", body = Vec<String>)
+    )
+)]
+pub async fn list_sessions_handler(State(sessions): State<Sessions>) -> Json<Vec<String>> {
+    Json(sessions.ids().await)
+}
+
+/// Delete the session
+#[utoipa::path(
+    delete,
+    path = "/sessions/{session_id}",
+    tag = "sessions",
+    params(("session_id" = String, Path, description = "Id of the session")),
+    responses(
+        (status = 204, description = "Session deleted"),
+        (status = 404, description = "Session not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_session_handler(
+    State(sessions): State<Sessions>,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    match sessions.remove(&session_id).await {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(AppError::new(
+            ErrorCode::SessionNotFound,
+            &format!("Session {} not found", session_id),
+        )),
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    app_data::AppStatus,
+    error::ErrorResponse,
+    request::{AppQuery, StateMachineRequest},
+    response::{StateMachineResponse, StateMachineStatus, StateMachineTransition},
+    router::ALLOWED_ROUTE_PATHES,
+    state_machine::{to_dot, StateMachineFormat, TRANSITIONS},
+};
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Mime type of Graphviz DOT
+const DOT_MIME_TYPE: &str = "text/vnd.graphviz";
+
+fn state_machine_response() -> StateMachineResponse {
+    StateMachineResponse {
+        initial_status: AppStatus::NotInitialized,
+        states: ALLOWED_ROUTE_PATHES
+            .iter()
+            .map(|(status, routes)| StateMachineStatus {
+                status: *status,
+                allowed_routes: routes.iter().map(|r| r.as_ref().to_string()).collect(),
+            })
+            .collect(),
+        transitions: TRANSITIONS
+            .iter()
+            .map(|t| StateMachineTransition {
+                from: t.from,
+                event: t.event,
+                to: t.to,
+                routes: t
+                    .event
+                    .routes()
+                    .iter()
+                    .map(|r| r.as_ref().to_string())
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// State machine of the status of the application, in JSON (default) or Graphviz DOT format
+#[utoipa::path(
+    get,
+    path = "/state-machine",
+    tag = "status",
+    params(StateMachineRequest),
+    responses(
+        (status = 200, description = "State machine in JSON or Graphviz DOT", content(
+            (StateMachineResponse = "application/json"),
+            (String = "text/vnd.graphviz")
+        )),
+        (status = 422, description = "Request not valid", body = ErrorResponse)
+    )
+)]
+pub async fn state_machine_handler(AppQuery(payload): AppQuery<StateMachineRequest>) -> Response {
+    match payload.format {
+        StateMachineFormat::Json => Json(state_machine_response()).into_response(),
+        StateMachineFormat::Dot => {
+            ([(header::CONTENT_TYPE, DOT_MIME_TYPE)], to_dot()).into_response()
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    app_data::{AppDataLockArc, VerificationPeriodDef},
+    error::{AppError, ErrorCode, ErrorResponse, ResultExt},
+    response::{VerificationCategoryResponse, VerificationDescription, VerificationsResponse},
+};
+use axum::{Extension, Json};
+use rust_ev_verifier_lib::verification::{VerificationMetaDataList, VerificationPeriod};
+
+fn verifications_response(
+    metadata: &VerificationMetaDataList,
+    period: &VerificationPeriod,
+) -> VerificationsResponse {
+    let mut categories: Vec<VerificationCategoryResponse> = vec![];
+    for md in metadata.iter().filter(|md| md.period() == period) {
+        let category = md.category().as_ref().to_string();
+        let description = VerificationDescription {
+            id: md.id().to_string(),
+            name: md.name().to_string(),
+            category: category.clone(),
+            description: md.description().to_string(),
+        };
+        match categories.iter_mut().find(|c| c.category == category) {
+            Some(c) => c.verifications.push(description),
+            None => categories.push(VerificationCategoryResponse {
+                category,
+                verifications: vec![description],
+            }),
+        }
+    }
+    VerificationsResponse {
+        verfification_period: VerificationPeriodDef::from(period),
+        categories,
+    }
+}
+
+/// Catalogue of the verifications of the period, grouped by category
+#[utoipa::path(
+    get,
+    path = "/verifications",
+    tag = "results",
+    responses(
+        (status = 200, description = "Catalogue of the verifications", body = VerificationsResponse),
+        (status = 409, description = "Route not allowed in the actual status", body = ErrorResponse)
+    )
+)]
+pub async fn verifications_handler(
+    Extension(state): Extension<AppDataLockArc>,
+) -> Result<Json<VerificationsResponse>, AppError> {
+    let state_read = state.read().await;
+    let period = state_read.verfification_period.ok_or_else(|| {
+        AppError::new(
+            ErrorCode::InternalError,
+            "The verification period is not set",
+        )
+    })?;
+    let metadata = VerificationMetaDataList::load(state_read.config.get_verification_list_str())
+        .app_err(
+            ErrorCode::MissingConfig,
+            "Error loading the list of verifications",
+        )?;
+    Ok(Json(verifications_response(&metadata, &period)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CONFIG;
+
+    #[test]
+    fn test_verifications_response() {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let res = verifications_response(&metadata, &VerificationPeriod::Tally);
+        assert_eq!(res.verfification_period, VerificationPeriodDef::Tally);
+        assert!(!res.categories.is_empty());
+        let ids = res
+            .categories
+            .iter()
+            .flat_map(|c| c.verifications.iter().map(|v| v.id.as_str()))
+            .collect::<Vec<_>>();
+        let mut expected = metadata.id_list_for_period(&VerificationPeriod::Tally);
+        let mut sorted_ids = ids.clone();
+        sorted_ids.sort();
+        expected.sort();
+        assert_eq!(sorted_ids, expected);
+        for c in res.categories.iter() {
+            assert!(c.verifications.iter().all(|v| v.category == c.category));
+        }
+    }
+}
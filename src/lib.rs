@@ -0,0 +1,506 @@
+pub mod app_data;
+pub mod auth;
+mod cli;
+pub mod client;
+pub mod error;
+pub mod files;
+pub mod fingerprint;
+mod handler;
+mod metrics;
+mod middlewares;
+mod persistence;
+pub mod report;
+pub mod request;
+pub mod response;
+pub mod router;
+pub mod session;
+pub mod state_machine;
+pub mod timeline;
+pub mod tls;
+mod tracing_subscriber;
+
+#[cfg(test)]
+mod test_client;
+#[cfg(test)]
+mod test_request;
+
+use anyhow::anyhow;
+use app_data::{AppData, AppDataLockArc};
+use auth::{AuthConfig, AuthToken, PUBLIC_DOCS_VAR, TOKEN_FILE_NAME};
+use axum::{middleware, Extension, Router};
+use clap::Parser;
+use cli::{verify, Cli, Command};
+use lazy_static::lazy_static;
+use middlewares::{auth_middleware, MakeMetricsClassifier};
+use router::{docs_routes, routes, session_routes};
+use rust_ev_verifier_lib::Config as VerifierConfig;
+use session::Sessions;
+use std::{
+    net::{IpAddr, SocketAddr},
+    process::ExitCode,
+};
+use tls::TlsConfig;
+use tower_http::trace::TraceLayer;
+use tracing::{error, info, warn};
+use tracing_subscriber::init_subscriber;
+
+/// Name of the file, in the data directory, where the state of the application is persisted
+const STATE_FILE_NAME: &str = "app_state.json";
+
+/// Name of the directory, in the data directory, where the state of the sessions are persisted
+const SESSIONS_DIR_NAME: &str = "sessions";
+
+/// Address of the listener if `APP_BIND_ADDRESS` is not set
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+
+lazy_static! {
+    static ref CONFIG: VerifierConfig = VerifierConfig::new(".");
+}
+
+/// Run the backend with the arguments of the command line
+pub async fn run() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+
+    let _ = dotenvy::dotenv().map_err(|e| {
+        let error = anyhow!(format!("Error reading .env file: {e}"));
+        error
+    })?;
+
+    let _guards = init_subscriber(&CONFIG);
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve().await.map(|_| ExitCode::SUCCESS),
+        Command::Verify(args) => Ok(verify(&args).await),
+    }
+}
+
+async fn serve() -> anyhow::Result<()> {
+    info!(
+        "Starting the backend of the Verifier GUI (Version: {})",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let shared_app_data: AppDataLockArc =
+        AppData::new_persisted(&CONFIG.data_dir_path().join(STATE_FILE_NAME));
+    let sessions = Sessions::new(Some(CONFIG.data_dir_path().join(SESSIONS_DIR_NAME)));
+
+    let port = dotenvy::var("APP_PORT").map_err(|e| {
+        error!("port (APP_PORT) not found in .env {}", e);
+        anyhow!(e)
+    })?;
+
+    let token = AuthToken::generate();
+    let token_file = CONFIG.data_dir_path().join(TOKEN_FILE_NAME);
+    token.write_to_file(&token_file).map_err(|e| {
+        error!(
+            "Error writing the token file {}: {}",
+            token_file.display(),
+            e
+        );
+        anyhow!(e)
+    })?;
+    info!("Token of the API written in {}", token_file.display());
+    let public_docs = match dotenvy::var(PUBLIC_DOCS_VAR) {
+        Ok(v) => v.parse::<bool>().map_err(|e| {
+            error!("{} not valid: {}", PUBLIC_DOCS_VAR, e);
+            anyhow!(e)
+        })?,
+        Err(_) => false,
+    };
+    if public_docs {
+        info!("The documentation of the API is served without token");
+    }
+
+    let bind_address = dotenvy::var("APP_BIND_ADDRESS")
+        .unwrap_or(DEFAULT_BIND_ADDRESS.to_string())
+        .parse::<IpAddr>()
+        .map_err(|e| {
+            error!("Bind address (APP_BIND_ADDRESS) not valid: {}", e);
+            anyhow!(e)
+        })?;
+    let port = port.parse::<u16>().map_err(|e| {
+        error!("port (APP_PORT) not valid: {}", e);
+        anyhow!(e)
+    })?;
+    let addr = SocketAddr::new(bind_address, port);
+    let tls = TlsConfig::from_env()?;
+    let app = app_with_auth(
+        shared_app_data,
+        sessions,
+        AuthConfig::new(token).with_public_docs(public_docs),
+    );
+
+    match tls {
+        Some(tls) => {
+            let tls_config = tls.load().inspect_err(|e| error!("{:#}", e))?;
+            info!("listening on https://{}", addr);
+            axum_server::bind_openssl(addr, tls_config)
+                .serve(app.into_make_service())
+                .await
+        }
+        None => {
+            if !bind_address.is_loopback() {
+                warn!(
+                    "Listening on {} without TLS: the token and the datasets are sent unencrypted",
+                    bind_address
+                );
+            }
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            info!("listening on http://{}", listener.local_addr()?);
+            axum::serve(listener, app).await
+        }
+    }
+    .map_err(|e| {
+        let error = anyhow!(format!("Error in serve: {}", e));
+        error
+    })
+}
+
+pub fn app(shared_app_data: AppDataLockArc) -> Router {
+    app_with_sessions(shared_app_data, Sessions::default())
+}
+
+/// Application with the default verification (routes without prefix) and the sessions
+pub fn app_with_sessions(shared_app_data: AppDataLockArc, sessions: Sessions) -> Router {
+    routes()
+        .layer(Extension(shared_app_data))
+        .merge(session_routes(sessions))
+        .merge(docs_routes())
+        .layer(TraceLayer::new(MakeMetricsClassifier))
+}
+
+/// Application of [app_with_sessions], where the requests must contain the bearer token of
+/// the configuration
+pub fn app_with_auth(
+    shared_app_data: AppDataLockArc,
+    sessions: Sessions,
+    auth: AuthConfig,
+) -> Router {
+    app_with_sessions(shared_app_data, sessions)
+        .layer(middleware::from_fn_with_state(auth, auth_middleware))
+}
+
+#[cfg(test)]
+mod test_helpers {
+    use super::*;
+    use app_data::VerificationPeriodDef;
+    use axum::{
+        body::Body,
+        http::{self, Request, Response, StatusCode},
+    };
+    use std::path::Path;
+    use tower::ServiceExt;
+
+    pub fn get_data_app() -> (AppDataLockArc, Router) {
+        let shared_app_data: AppDataLockArc = AppData::new();
+        (shared_app_data.clone(), app(shared_app_data.clone()))
+    }
+
+    pub fn is_response_ok<T>(response: &Response<T>) {
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    pub fn is_response_json<T>(response: &Response<T>) {
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+    }
+
+    pub async fn call_create_session(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/sessions")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_delete_session(app: &Router, session_id: &str) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::DELETE)
+                    .uri(format!("/sessions/{}", session_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Call the uri in the session
+    pub async fn call_in_session(
+        app: &Router,
+        session_id: &str,
+        method: http::Method,
+        uri: &str,
+        json_body: Option<String>,
+    ) -> Response<Body> {
+        let request = Request::builder()
+            .method(method)
+            .uri(format!("/sessions/{}{}", session_id, uri));
+        let request = match json_body {
+            Some(body) => request
+                .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(body)),
+            None => request.body(Body::empty()),
+        };
+        app.clone().oneshot(request.unwrap()).await.unwrap()
+    }
+
+    pub async fn call_status(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/status")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_init(app: &Router, period: VerificationPeriodDef) -> Response<Body> {
+        let body = format!("{{\"period\": \"{}\"}}", period.as_ref());
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/init")
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_events(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/events")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_metrics(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_verifications(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/verifications")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_manual_checks(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/manual-checks")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_report(app: &Router, format: &str) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/report?format={}", format))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_state_machine(app: &Router, format: &str) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/state-machine?format={}", format))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_reset(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/reset")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_extract(app: &Router) -> Response<Body> {
+        let password = dotenvy::var("APP_VERIFIER_DATASET_PASSWORD").unwrap();
+        call_with_password(app, "/extract", &password).await
+    }
+
+    pub async fn call_with_password(app: &Router, uri: &str, password: &str) -> Response<Body> {
+        let body = format!("{{\"password\": \"{}\"}}", password);
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(uri)
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Call the uri with a POST request and the body, of the given content type
+    pub async fn call_with_body(
+        app: &Router,
+        uri: &str,
+        content_type: &str,
+        body: &str,
+    ) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(uri)
+                    .header(http::header::CONTENT_TYPE, content_type)
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_cancel(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/cancel")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Call `/run/{verification}`, with `failed` or the id of a verification
+    pub async fn call_run_again(app: &Router, verification: &str) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(format!("/run/{}", verification))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_back(app: &Router) -> Response<Body> {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/back")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_input_file(app: &Router, path: &Path, uri_path: &str) -> Response<Body> {
+        let body = format!("{{\"path\": \"{}\"}}", path.to_str().unwrap());
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(uri_path)
+                    .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_upload_file(app: &Router, path: &Path, uri_path: &str) -> Response<Body> {
+        let boundary = "verifier-test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/zip\r\n\r\n",
+            path.file_name().unwrap().to_str().unwrap()
+        )
+        .into_bytes();
+        body.extend(std::fs::read(path).unwrap());
+        body.extend(format!("\r\n--{boundary}--\r\n").into_bytes());
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(uri_path)
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        format!("multipart/form-data; boundary={boundary}"),
+                    )
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_files(app: &Router, path: Option<&str>) -> Response<Body> {
+        let uri = match path {
+            Some(p) => format!("/files?path={}", p),
+            None => "/files".to_string(),
+        };
+        app.clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    pub async fn call_input_context(app: &Router, path: &Path) -> Response<Body> {
+        call_input_file(app, path, "/context-dataset").await
+    }
+
+    pub async fn call_input_period_dataset(app: &Router, path: &Path) -> Response<Body> {
+        call_input_file(app, path, "/period-dataset").await
+    }
+}
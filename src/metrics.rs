@@ -0,0 +1,219 @@
+use crate::{
+    app_data::{AppData, AppStatus, VerificationStatusEnum},
+    error::{AppError, ErrorCode, ResultExt},
+};
+use chrono::{DateTime, Local};
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+/// Prefix of the names of the metrics
+const PREFIX: &str = "verifier";
+
+/// Buckets (in seconds) of the durations of the extraction and of the run
+const STEP_DURATION_BUCKETS: &[f64] = &[
+    1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0,
+];
+
+lazy_static! {
+    /// Metrics of the process, common to all the verifications (default and sessions)
+    pub static ref METRICS: Metrics = Metrics::new();
+}
+
+/// Metrics collected by the middleware and by the changes of status
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    extraction_duration: Histogram,
+    run_duration: Histogram,
+    extraction_errors: IntCounter,
+    run_errors: IntCounter,
+}
+
+fn opts(name: &str, help: &str) -> Opts {
+    Opts::new(name, help).namespace(PREFIX)
+}
+
+fn duration_secs(start: Option<DateTime<Local>>, end: DateTime<Local>) -> Option<f64> {
+    start.map(|s| (end - s).num_milliseconds() as f64 / 1000.0)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        // The names and labels are constant and registered once. The registration cannot fail
+        let http_requests = IntCounterVec::new(
+            opts("http_requests_total", "Number of HTTP requests"),
+            &["route", "method", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::from(opts(
+                "http_request_duration_seconds",
+                "Latency of the HTTP requests",
+            )),
+            &["route", "method"],
+        )
+        .unwrap();
+        let extraction_duration = Histogram::with_opts(
+            HistogramOpts::from(opts(
+                "extraction_duration_seconds",
+                "Duration of the extractions",
+            ))
+            .buckets(STEP_DURATION_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let run_duration = Histogram::with_opts(
+            HistogramOpts::from(opts("run_duration_seconds", "Duration of the runs"))
+                .buckets(STEP_DURATION_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let extraction_errors = IntCounter::with_opts(opts(
+            "extraction_errors_total",
+            "Number of failed extractions",
+        ))
+        .unwrap();
+        let run_errors =
+            IntCounter::with_opts(opts("run_errors_total", "Number of failed runs")).unwrap();
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(extraction_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(run_duration.clone())).unwrap();
+        registry
+            .register(Box::new(extraction_errors.clone()))
+            .unwrap();
+        registry.register(Box::new(run_errors.clone())).unwrap();
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            extraction_duration,
+            run_duration,
+            extraction_errors,
+            run_errors,
+        }
+    }
+
+    /// Observe a request. The route is the path of the route (e.g. `/run/:verification_id`)
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, latency: Duration) {
+        self.http_requests
+            .with_label_values(&[route, method, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[route, method])
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Observe the end of an extraction, with the status set to `Extracted` or `ExtractError`
+    ///
+    /// Must only be called where the extraction ends, not for the other changes of status
+    /// (e.g. `/back`)
+    pub fn observe_extraction(&self, data: &AppData) {
+        if let Some(d) = duration_secs(
+            data.extraction_started_at,
+            data.extraction_finished_at.unwrap_or(Local::now()),
+        ) {
+            self.extraction_duration.observe(d);
+        }
+        if data.app_status == AppStatus::ExtractError {
+            self.extraction_errors.inc();
+        }
+    }
+
+    /// Observe the end of a run, with the status set to `Finished` or `RunError`
+    ///
+    /// Must only be called where the run ends, not for the other changes of status
+    pub fn observe_run(&self, data: &AppData) {
+        if let Some(d) = duration_secs(
+            data.run_started_at,
+            data.run_finished_at.unwrap_or(Local::now()),
+        ) {
+            self.run_duration.observe(d);
+        }
+        if data.app_status == AppStatus::RunError {
+            self.run_errors.inc();
+        }
+    }
+
+    /// Render the metrics in the Prometheus text format
+    ///
+    /// The status of the application and the number of verifications per status are
+    /// taken from the data
+    pub fn render(&self, data: &AppData) -> Result<String, AppError> {
+        let registry = Registry::new();
+        let app_status = IntGaugeVec::new(
+            opts(
+                "app_status",
+                "Actual status of the application (1 if active)",
+            ),
+            &["status"],
+        )
+        .app_err(ErrorCode::InternalError, "Error creating the metrics")?;
+        for status in AppStatus::iter() {
+            app_status
+                .with_label_values(&[status.as_ref()])
+                .set((status == data.app_status) as i64);
+        }
+        let verifications = IntGaugeVec::new(
+            opts("verifications", "Number of verifications per status"),
+            &["status"],
+        )
+        .app_err(ErrorCode::InternalError, "Error creating the metrics")?;
+        for status in VerificationStatusEnum::iter() {
+            verifications.with_label_values(&[status.as_ref()]).set(
+                data.verification_status
+                    .values()
+                    .filter(|v| v.status == status)
+                    .count() as i64,
+            );
+        }
+        registry
+            .register(Box::new(app_status))
+            .and_then(|_| registry.register(Box::new(verifications)))
+            .app_err(ErrorCode::InternalError, "Error registering the metrics")?;
+
+        let mut families = self.registry.gather();
+        families.extend(registry.gather());
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&families, &mut buffer)
+            .app_err(ErrorCode::InternalError, "Error encoding the metrics")?;
+        String::from_utf8(buffer).app_err(ErrorCode::InternalError, "Error encoding the metrics")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.observe_request("/status", "GET", 200, Duration::from_millis(3));
+        let mut data = AppData::default();
+        data.app_status = AppStatus::ExtractError;
+        data.extraction_started_at = Some(Local::now() - chrono::TimeDelta::seconds(2));
+        metrics.observe_extraction(&data);
+
+        let text = metrics.render(&data).unwrap();
+        assert!(text.contains(
+            "verifier_http_requests_total{method=\"GET\",route=\"/status\",status=\"200\"} 1"
+        ));
+        assert!(text.contains("verifier_http_request_duration_seconds_count"));
+        assert!(text.contains("verifier_extraction_duration_seconds_count 1"));
+        assert!(text.contains("verifier_extraction_errors_total 1"));
+        assert!(text.contains("verifier_run_errors_total 0"));
+        assert!(text.contains("verifier_app_status{status=\"ExtractError\"} 1"));
+        assert!(text.contains("verifier_app_status{status=\"Running\"} 0"));
+        assert!(text.contains("verifier_verifications{status=\"NotStarted\"} 0"));
+    }
+}
@@ -1,32 +1,63 @@
 use crate::{
     app_data::{AppDataLockArc, AppStatus},
-    response::response_error_with_status,
-    router::{RoutePath, ALLOWED_ROUTE_PATHES},
+    auth::AuthConfig,
+    error::{AppError, ErrorCode},
+    metrics::METRICS,
+    router::{RoutePath, ALLOWED_ROUTE_PATHES, DOCS_PATH, OPENAPI_PATH, SESSION_PATH},
+    session::Sessions,
 };
 use axum::{
-    extract::{Request, State},
-    http::StatusCode,
+    extract::{MatchedPath, Path, Request, State},
+    http::{self, header::AUTHORIZATION},
     middleware::Next,
     response::{IntoResponse, Response},
+    Extension,
+};
+use std::{collections::HashMap, fmt::Display, str::FromStr, time::Instant};
+use tower_http::classify::{
+    ClassifiedResponse, ClassifyResponse, MakeClassifier, NeverClassifyEos, ServerErrorsAsFailures,
+    ServerErrorsFailureClass,
 };
-use std::str::FromStr;
 
-fn validate_uri_with_status(path: &RoutePath, status: &AppStatus) -> String {
+fn validate_uri_with_status(path: &RoutePath, status: &AppStatus) -> Result<(), AppError> {
     match ALLOWED_ROUTE_PATHES.iter().find(|(s, _)| s == status) {
         Some((_, ps)) => match ps.contains(path) {
-            true => String::default(),
-            false => format!(
-                "Path {} not allowed for status {}",
-                path.as_ref(),
-                status.as_ref()
-            ),
+            true => Ok(()),
+            false => Err(AppError::new(
+                ErrorCode::InvalidStateTransition,
+                &format!(
+                    "Path {} not allowed for status {}",
+                    path.as_ref(),
+                    status.as_ref()
+                ),
+            )),
         },
-        None => format!("Status {} not allowed", status.as_ref()),
+        None => Err(AppError::new(
+            ErrorCode::InternalError,
+            &format!("Status {} not allowed", status.as_ref()),
+        )),
     }
 }
 
+/// Path of the route of the request, without the prefix of the session
+///
+/// The matched path contains the parameters as in the route (e.g. `/run/:verification_id`)
+fn route_path<B>(request: &http::Request<B>) -> String {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or(request.uri().path());
+    match path.strip_prefix(SESSION_PATH) {
+        Some("") => "/",
+        Some(p) => p,
+        None => path,
+    }
+    .to_string()
+}
+
 pub async fn check_status_middelware(
-    State(state): State<AppDataLockArc>,
+    Extension(state): Extension<AppDataLockArc>,
     // you can add more extractors here but the last
     // extractor must implement `FromRequest` which
     // `Request` does
@@ -34,23 +65,137 @@ pub async fn check_status_middelware(
     next: Next,
 ) -> Response {
     {
-        let path = request.uri().path();
+        let path = route_path(&request);
         let status = &state.read().await.app_status;
-        let path_enum = match RoutePath::from_str(path) {
-            Ok(p) => p,
-            Err(_) => {
-                return response_error_with_status(
-                    StatusCode::BAD_REQUEST,
-                    &format!("Path {} not found", path),
-                )
-                .into_response()
+        // The layer only runs for the routes of [RoutePath]: the unknown routes go to the
+        // fallback
+        if let Ok(path_enum) = RoutePath::from_str(&path) {
+            if let Err(e) = validate_uri_with_status(&path_enum, status) {
+                return e.into_response();
             }
-        };
-        let validation_result = validate_uri_with_status(&path_enum, status);
-        if !validation_result.is_empty() {
-            return response_error_with_status(StatusCode::BAD_REQUEST, &validation_result)
-                .into_response();
         }
     }
     next.run(request).await
 }
+
+/// Reject the requests without the bearer token of the launch
+///
+/// The health check `/` stays open. The documentation of the API is only open if
+/// configured in [AuthConfig]
+pub async fn auth_middleware(
+    State(auth): State<AuthConfig>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    let is_docs = path == OPENAPI_PATH || path.starts_with(DOCS_PATH);
+    if path == RoutePath::Root.as_ref() || (auth.public_docs() && is_docs) {
+        return next.run(request).await;
+    }
+    let valid = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| auth.token().is_valid_header(h));
+    match valid {
+        true => next.run(request).await,
+        false => AppError::new(
+            ErrorCode::Unauthorized,
+            &format!("Missing or invalid token for {}", request.uri().path()),
+        )
+        .into_response(),
+    }
+}
+
+/// Label of the route of the requests not matching any route, to not create a metric per
+/// unknown path
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Classifier of the [TraceLayer](tower_http::trace::TraceLayer), collecting the number and
+/// the latency of the requests per route for the metrics
+///
+/// The responses are classified as with [ServerErrorsAsFailures]. The layer must be added
+/// with [Router::layer](axum::Router::layer), which runs after the routing, so that the
+/// matched path is known
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MakeMetricsClassifier;
+
+/// Classifier of one request, created by [MakeMetricsClassifier]
+#[derive(Debug, Clone)]
+pub struct MetricsClassifier {
+    route: String,
+    method: String,
+    start: Instant,
+}
+
+impl MakeClassifier for MakeMetricsClassifier {
+    type Classifier = MetricsClassifier;
+    type FailureClass = ServerErrorsFailureClass;
+    type ClassifyEos = NeverClassifyEos<ServerErrorsFailureClass>;
+
+    fn make_classifier<B>(&self, request: &http::Request<B>) -> Self::Classifier {
+        let route = match request.extensions().get::<MatchedPath>() {
+            Some(_) => route_path(request),
+            None => UNMATCHED_ROUTE.to_string(),
+        };
+        MetricsClassifier {
+            route,
+            method: request.method().to_string(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl ClassifyResponse for MetricsClassifier {
+    type FailureClass = ServerErrorsFailureClass;
+    type ClassifyEos = NeverClassifyEos<ServerErrorsFailureClass>;
+
+    fn classify_response<B>(
+        self,
+        response: &http::Response<B>,
+    ) -> ClassifiedResponse<Self::FailureClass, Self::ClassifyEos> {
+        METRICS.observe_request(
+            &self.route,
+            &self.method,
+            response.status().as_u16(),
+            self.start.elapsed(),
+        );
+        ServerErrorsAsFailures::new().classify_response(response)
+    }
+
+    fn classify_error<E>(self, error: &E) -> Self::FailureClass
+    where
+        E: Display + 'static,
+    {
+        ServerErrorsAsFailures::new().classify_error(error)
+    }
+}
+
+/// Name of the path parameter containing the id of the session
+pub const SESSION_ID_PARAM: &str = "session_id";
+
+/// Put the [AppData](crate::app_data::AppData) of the session in the extensions of the request
+///
+/// The session id is taken from the path parameter [SESSION_ID_PARAM]
+pub async fn session_middleware(
+    State(sessions): State<Sessions>,
+    Path(params): Path<HashMap<String, String>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let session_id = params
+        .get(SESSION_ID_PARAM)
+        .map(String::as_str)
+        .unwrap_or_default();
+    match sessions.get(session_id).await {
+        Some(state) => {
+            request.extensions_mut().insert(state);
+            next.run(request).await
+        }
+        None => AppError::new(
+            ErrorCode::SessionNotFound,
+            &format!("Session {} not found", session_id),
+        )
+        .into_response(),
+    }
+}
@@ -0,0 +1,133 @@
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+enum Message<T> {
+    Write(T),
+    Remove,
+    /// Answered when the previous messages have been processed
+    Flush(oneshot::Sender<()>),
+}
+
+/// Writer of a state file in a background thread
+///
+/// The states are serialized and written by the thread, so that the callers (holding the lock
+/// of the state) don't wait for the file system. If several states are waiting, only the last
+/// one is written. The thread ends when the writer is dropped
+#[derive(Debug)]
+pub struct StateWriter<T> {
+    state_file: PathBuf,
+    sender: mpsc::Sender<Message<T>>,
+}
+
+impl<T: Serialize + Send + 'static> StateWriter<T> {
+    pub fn new(state_file: &Path) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let file = state_file.to_path_buf();
+        std::thread::spawn(move || run(&file, receiver));
+        Self {
+            state_file: state_file.to_path_buf(),
+            sender,
+        }
+    }
+
+    pub fn state_file(&self) -> &Path {
+        &self.state_file
+    }
+
+    /// Write the state in the background
+    pub fn write(&self, state: T) {
+        let _ = self.sender.send(Message::Write(state));
+    }
+
+    /// Remove the state file in the background, after the pending writes
+    pub fn remove(&self) {
+        let _ = self.sender.send(Message::Remove);
+    }
+
+    /// The receiver is notified when the pending writes and removals are done
+    pub fn flush(&self) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        let _ = self.sender.send(Message::Flush(sender));
+        receiver
+    }
+}
+
+fn run<T: Serialize>(state_file: &Path, receiver: mpsc::Receiver<Message<T>>) {
+    while let Ok(first) = receiver.recv() {
+        // Only the last write or removal of the waiting messages is relevant
+        let mut last = None;
+        let mut flushes = vec![];
+        for message in std::iter::once(first).chain(receiver.try_iter()) {
+            match message {
+                Message::Flush(sender) => flushes.push(sender),
+                m => last = Some(m),
+            }
+        }
+        match last {
+            Some(Message::Write(state)) => write(state_file, &state),
+            Some(Message::Remove) => {
+                if let Err(e) = std::fs::remove_file(state_file) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        warn!(
+                            "State file {} cannot be removed: {}",
+                            state_file.display(),
+                            e
+                        );
+                    }
+                }
+            }
+            _ => (),
+        }
+        for sender in flushes {
+            let _ = sender.send(());
+        }
+    }
+}
+
+/// The file is written in a temporary file and then renamed, so that a crash
+/// cannot leave a corrupted state file. An error is only logged
+fn write<T: Serialize>(state_file: &Path, state: &T) {
+    let res = serde_json::to_string(state)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            if let Some(dir) = state_file.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            let tmp_file = state_file.with_extension("tmp");
+            std::fs::write(&tmp_file, content).map_err(|e| e.to_string())?;
+            std::fs::rename(&tmp_file, state_file).map_err(|e| e.to_string())
+        });
+    if let Err(e) = res {
+        warn!(
+            "State cannot be persisted in {}: {}",
+            state_file.display(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_state_writer() {
+        let file = std::env::temp_dir().join(format!("verifier-state-{}.json", Uuid::new_v4()));
+        let writer = StateWriter::new(&file);
+        for i in 0..100 {
+            writer.write(vec![i]);
+        }
+        writer.flush().blocking_recv().unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "[99]");
+        writer.write(vec![100]);
+        writer.remove();
+        writer.flush().blocking_recv().unwrap();
+        assert!(!file.exists());
+    }
+}
@@ -0,0 +1,128 @@
+use super::{format_path, format_sha256, format_timestamp, status_label, ReportData};
+use crate::app_data::VerificationStatusEnum;
+use std::fmt::Write;
+
+const STYLE: &str = "body{font-family:Helvetica,Arial,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;width:100%;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#eee}\
+.ok{color:#1a7f37}.nok{color:#cf222e}.other{color:#6e7781}";
+
+/// Escape the characters with a special meaning in HTML
+fn escape(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+fn status_class(status: VerificationStatusEnum) -> &'static str {
+    match status {
+        VerificationStatusEnum::FinishedSuccessfully => "ok",
+        VerificationStatusEnum::FinishedWithFailures
+        | VerificationStatusEnum::FinishedWithErrors
+        | VerificationStatusEnum::FinishedWithFailureAndErrors => "nok",
+        _ => "other",
+    }
+}
+
+fn messages(list: &[String]) -> String {
+    list.iter()
+        .map(|m| escape(m))
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+/// Render the report as a self-contained HTML page
+pub(super) fn render(data: &ReportData) -> String {
+    let mut res = String::new();
+    // Writing in a String cannot fail
+    let _ = write!(
+        res,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+<title>Verification report - {period}</title><style>{STYLE}</style></head><body>\
+<h1>Verification report</h1><h2>Information</h2><table>\
+<tr><th>Period</th><td>{period}</td></tr>\
+<tr><th>Context dataset</th><td>{context}</td></tr>\
+<tr><th>Context dataset SHA-256</th><td>{context_sha256}</td></tr>\
+<tr><th>Period dataset</th><td>{period_file}</td></tr>\
+<tr><th>Period dataset SHA-256</th><td>{period_sha256}</td></tr>\
+<tr><th>Run started at</th><td>{started}</td></tr>\
+<tr><th>Run finished at</th><td>{finished}</td></tr>\
+<tr><th>Report generated at</th><td>{generated}</td></tr>\
+<tr><th>Backend version</th><td>{backend}</td></tr>\
+<tr><th>Verifier version</th><td>{verifier}</td></tr></table>",
+        period = escape(&data.period),
+        context = escape(&format_path(&data.context_zip_file)),
+        context_sha256 = escape(&format_sha256(&data.context_sha256)),
+        period_file = escape(&format_path(&data.period_zip_file)),
+        period_sha256 = escape(&format_sha256(&data.period_sha256)),
+        started = format_timestamp(data.run_started_at),
+        finished = format_timestamp(data.run_finished_at),
+        generated = format_timestamp(Some(data.generated_at)),
+        backend = escape(&data.backend_version),
+        verifier = escape(&data.verifier_version),
+    );
+    res.push_str("<h2>Summary</h2><table><tr><th>Status</th><th>Number</th></tr>");
+    for (label, n) in data.summary() {
+        let _ = write!(res, "<tr><td>{}</td><td>{}</td></tr>", label, n);
+    }
+    let _ = write!(
+        res,
+        "<tr><th>Total</th><th>{}</th></tr></table>",
+        data.verifications.len()
+    );
+    res.push_str(
+        "<h2>Verifications</h2><table><tr><th>Id</th><th>Name</th><th>Category</th>\
+<th>Status</th><th>Failures</th><th>Errors</th></tr>",
+    );
+    for v in data.verifications.iter() {
+        let _ = write!(
+            res,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&v.id),
+            escape(&v.name),
+            escape(&v.category),
+            status_class(v.status),
+            status_label(v.status),
+            messages(&v.failures),
+            messages(&v.errors)
+        );
+    }
+    res.push_str("</table></body></html>");
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::report_data;
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let html = render(&report_data());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("VerifySignature&lt;Setup&gt;"));
+        assert!(html.contains("Value &lt;a&gt; is not correct"));
+        assert!(html.contains("./datasets/context.zip"));
+        assert!(html
+            .contains("<td>ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad</td>"));
+        assert!(html.ends_with("</html>"));
+    }
+}
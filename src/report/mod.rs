@@ -0,0 +1,230 @@
+mod html;
+mod pdf;
+
+use crate::app_data::{AppData, VerificationStatusEnum};
+use chrono::{DateTime, Local};
+use rust_ev_verifier_lib::verification::VerificationPeriod;
+use serde::Deserialize;
+use std::path::PathBuf;
+use strum::{AsRefStr, EnumString};
+use utoipa::ToSchema;
+
+/// Version of the verifier library, read from `Cargo.lock` by the build script
+///
+/// The library does not expose its version
+pub const VERIFIER_LIB_VERSION: &str = env!("VERIFIER_LIB_VERSION");
+
+/// Format of the generated report
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, AsRefStr, EnumString, Deserialize, ToSchema,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    Pdf,
+}
+
+/// Verification as shown in the report
+#[derive(Debug, Clone)]
+pub struct ReportVerification {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub status: VerificationStatusEnum,
+    pub failures: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Snapshot of the data needed to generate the report
+///
+/// The snapshot is taken under the lock, so that the rendering can be done without it
+#[derive(Debug, Clone)]
+pub struct ReportData {
+    pub period: String,
+    pub context_zip_file: Option<PathBuf>,
+    pub period_zip_file: Option<PathBuf>,
+    pub context_sha256: Option<String>,
+    pub period_sha256: Option<String>,
+    pub run_started_at: Option<DateTime<Local>>,
+    pub run_finished_at: Option<DateTime<Local>>,
+    pub generated_at: DateTime<Local>,
+    pub backend_version: String,
+    pub verifier_version: String,
+    pub verifications: Vec<ReportVerification>,
+}
+
+impl From<&AppData> for ReportData {
+    fn from(value: &AppData) -> Self {
+        let mut verifications = value
+            .verification_information
+            .values()
+            .map(|info| {
+                let status = value.verification_status.get(&info.id);
+                ReportVerification {
+                    id: info.id.clone(),
+                    name: info.name.clone(),
+                    category: info.category.clone(),
+                    status: status.map(|s| s.status).unwrap_or_default(),
+                    failures: status.map(|s| s.failures.clone()).unwrap_or_default(),
+                    errors: status.map(|s| s.errors.clone()).unwrap_or_default(),
+                }
+            })
+            .collect::<Vec<_>>();
+        verifications.sort_by(|a, b| a.id.cmp(&b.id));
+        let location = &value.input_file_location;
+        let (period_zip_file, period_sha256) = match value.verfification_period {
+            Some(VerificationPeriod::Setup) => (
+                location.setup_zip_file.clone(),
+                location.setup_sha256.clone(),
+            ),
+            Some(VerificationPeriod::Tally) => (
+                location.tally_zip_file.clone(),
+                location.tally_sha256.clone(),
+            ),
+            None => (None, None),
+        };
+        Self {
+            period: value
+                .verfification_period
+                .as_ref()
+                .map(|p| p.as_ref().to_string())
+                .unwrap_or_default(),
+            context_zip_file: location.context_zip_file.clone(),
+            period_zip_file,
+            context_sha256: location.context_sha256.clone(),
+            period_sha256,
+            run_started_at: value.run_started_at,
+            run_finished_at: value.run_finished_at,
+            generated_at: Local::now(),
+            backend_version: env!("CARGO_PKG_VERSION").to_string(),
+            verifier_version: VERIFIER_LIB_VERSION.to_string(),
+            verifications,
+        }
+    }
+}
+
+impl ReportData {
+    /// Number of verifications with the given status
+    pub fn count(&self, status: VerificationStatusEnum) -> usize {
+        self.verifications
+            .iter()
+            .filter(|v| v.status == status)
+            .count()
+    }
+
+    /// Summary of the statuses (label and number), without the statuses that don't occur
+    pub fn summary(&self) -> Vec<(&'static str, usize)> {
+        [
+            VerificationStatusEnum::FinishedSuccessfully,
+            VerificationStatusEnum::FinishedWithFailures,
+            VerificationStatusEnum::FinishedWithErrors,
+            VerificationStatusEnum::FinishedWithFailureAndErrors,
+            VerificationStatusEnum::Excluded,
+            VerificationStatusEnum::Cancelled,
+        ]
+        .into_iter()
+        .map(|s| (status_label(s), self.count(s)))
+        .filter(|(_, n)| *n > 0)
+        .collect()
+    }
+
+    /// Render the report in the given format
+    pub fn render(&self, format: ReportFormat) -> anyhow::Result<Vec<u8>> {
+        match format {
+            ReportFormat::Html => Ok(html::render(self).into_bytes()),
+            ReportFormat::Pdf => pdf::render(self),
+        }
+    }
+}
+
+/// Human readable label of the status of a verification
+pub fn status_label(status: VerificationStatusEnum) -> &'static str {
+    match status {
+        VerificationStatusEnum::NotStarted => "Not started",
+        VerificationStatusEnum::Running => "Running",
+        VerificationStatusEnum::FinishedSuccessfully => "Successful",
+        VerificationStatusEnum::FinishedWithFailures => "Failures",
+        VerificationStatusEnum::FinishedWithErrors => "Errors",
+        VerificationStatusEnum::FinishedWithFailureAndErrors => "Failures and errors",
+        VerificationStatusEnum::Cancelled => "Cancelled",
+        VerificationStatusEnum::Excluded => "Excluded",
+    }
+}
+
+fn format_timestamp(value: Option<DateTime<Local>>) -> String {
+    value
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or("-".to_string())
+}
+
+fn format_path(value: &Option<PathBuf>) -> String {
+    value
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or("-".to_string())
+}
+
+fn format_sha256(value: &Option<String>) -> String {
+    value.clone().unwrap_or("-".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    pub(super) fn report_data() -> ReportData {
+        ReportData {
+            period: "setup".to_string(),
+            context_zip_file: Some(PathBuf::from("./datasets/context.zip")),
+            period_zip_file: None,
+            context_sha256: Some(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string(),
+            ),
+            period_sha256: None,
+            run_started_at: Some(Local::now()),
+            run_finished_at: Some(Local::now()),
+            generated_at: Local::now(),
+            backend_version: "0.1.0".to_string(),
+            verifier_version: VERIFIER_LIB_VERSION.to_string(),
+            verifications: vec![
+                ReportVerification {
+                    id: "01.01".to_string(),
+                    name: "VerifySignature<Setup>".to_string(),
+                    category: "authenticity".to_string(),
+                    status: VerificationStatusEnum::FinishedSuccessfully,
+                    failures: vec![],
+                    errors: vec![],
+                },
+                ReportVerification {
+                    id: "02.01".to_string(),
+                    name: "VerifyConsistency".to_string(),
+                    category: "consistency".to_string(),
+                    status: VerificationStatusEnum::FinishedWithFailures,
+                    failures: vec!["Value <a> is not correct".to_string()],
+                    errors: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let data = report_data();
+        assert_eq!(data.summary(), vec![("Successful", 1), ("Failures", 1)]);
+    }
+
+    #[test]
+    fn test_verifier_lib_version() {
+        assert_ne!(VERIFIER_LIB_VERSION, "unknown");
+        assert_eq!(VERIFIER_LIB_VERSION.split('.').count(), 3);
+    }
+
+    #[test]
+    fn test_format() {
+        use std::str::FromStr;
+        assert_eq!(ReportFormat::from_str("pdf").unwrap(), ReportFormat::Pdf);
+        assert_eq!(ReportFormat::Html.as_ref(), "html");
+    }
+}
@@ -0,0 +1,166 @@
+use super::{format_path, format_sha256, format_timestamp, status_label, ReportData};
+use anyhow::anyhow;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const FONT_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 5.0;
+/// Maximal number of characters in a line with the font size [FONT_SIZE]
+///
+/// The builtin fonts don't provide the width of the characters, so the lines are wrapped
+/// with an approximation
+const MAX_LINE_CHARS: usize = 95;
+
+/// Writer of lines, adding new pages when necessary
+struct LineWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+}
+
+impl LineWriter {
+    fn new(title: &str) -> anyhow::Result<Self> {
+        let (doc, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        let regular = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| anyhow!(e))?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| anyhow!(e))?;
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            doc,
+            layer,
+            regular,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    fn new_page_if_necessary(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            let (page, layer) = self
+                .doc
+                .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn write(&mut self, text: &str, size: f32, bold: bool, indent: f32) {
+        let font = match bold {
+            true => self.bold.clone(),
+            false => self.regular.clone(),
+        };
+        let chars = text.chars().collect::<Vec<_>>();
+        let max_chars = ((MAX_LINE_CHARS as f32) * FONT_SIZE / size) as usize;
+        for chunk in chars.chunks(max_chars.max(1)) {
+            let height = LINE_HEIGHT * size / FONT_SIZE;
+            self.new_page_if_necessary(height);
+            self.y -= height;
+            self.layer.use_text(
+                chunk.iter().collect::<String>(),
+                size,
+                Mm(MARGIN + indent),
+                Mm(self.y),
+                &font,
+            );
+        }
+    }
+
+    fn title(&mut self, text: &str, size: f32) {
+        self.y -= LINE_HEIGHT / 2.0;
+        self.write(text, size, true, 0.0);
+        self.y -= LINE_HEIGHT / 2.0;
+    }
+
+    fn line(&mut self, text: &str) {
+        self.write(text, FONT_SIZE, false, 0.0);
+    }
+
+    fn finish(self) -> anyhow::Result<Vec<u8>> {
+        self.doc.save_to_bytes().map_err(|e| anyhow!(e))
+    }
+}
+
+/// Render the report as PDF document
+pub(super) fn render(data: &ReportData) -> anyhow::Result<Vec<u8>> {
+    let mut writer = LineWriter::new(&format!("Verification report - {}", data.period))?;
+    writer.title("Verification report", 18.0);
+    writer.title("Information", 14.0);
+    for (label, value) in [
+        ("Period", data.period.clone()),
+        ("Context dataset", format_path(&data.context_zip_file)),
+        (
+            "Context dataset SHA-256",
+            format_sha256(&data.context_sha256),
+        ),
+        ("Period dataset", format_path(&data.period_zip_file)),
+        ("Period dataset SHA-256", format_sha256(&data.period_sha256)),
+        ("Run started at", format_timestamp(data.run_started_at)),
+        ("Run finished at", format_timestamp(data.run_finished_at)),
+        (
+            "Report generated at",
+            format_timestamp(Some(data.generated_at)),
+        ),
+        ("Backend version", data.backend_version.clone()),
+        ("Verifier version", data.verifier_version.clone()),
+    ] {
+        writer.line(&format!("{}: {}", label, value));
+    }
+    writer.title("Summary", 14.0);
+    for (label, n) in data.summary() {
+        writer.line(&format!("{}: {}", label, n));
+    }
+    writer.line(&format!("Total: {}", data.verifications.len()));
+    writer.title("Verifications", 14.0);
+    for v in data.verifications.iter() {
+        writer.write(
+            &format!("{} {} ({})", v.id, v.name, v.category),
+            FONT_SIZE,
+            true,
+            0.0,
+        );
+        writer.write(
+            &format!("Status: {}", status_label(v.status)),
+            FONT_SIZE,
+            false,
+            5.0,
+        );
+        for f in v.failures.iter() {
+            writer.write(&format!("Failure: {}", f), FONT_SIZE, false, 5.0);
+        }
+        for e in v.errors.iter() {
+            writer.write(&format!("Error: {}", e), FONT_SIZE, false, 5.0);
+        }
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::report_data;
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let pdf = render(&report_data()).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn test_render_many_pages() {
+        let mut data = report_data();
+        let v = data.verifications[1].clone();
+        data.verifications = (0..200).map(|_| v.clone()).collect();
+        let pdf = render(&data).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+}
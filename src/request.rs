@@ -1,14 +1,148 @@
 use std::path::PathBuf;
 
-use crate::app_data::VerificationPeriodDef;
-use serde::Deserialize;
+use crate::{
+    app_data::{RunStrategyDef, VerificationPeriodDef},
+    error::{AppError, ErrorCode},
+    report::ReportFormat,
+    state_machine::StateMachineFormat,
+};
+use axum::{
+    async_trait,
+    body::{Body, Bytes},
+    extract::{FromRequest, FromRequestParts, Request},
+};
+use secrecy::SecretString;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize)]
+/// Name of the multipart field containing the uploaded dataset
+pub const UPLOAD_FIELD_NAME: &str = "file";
+
+/// Json extractor returning an [AppError] if the body is not valid
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct AppJson<T>(pub T);
+
+/// Json extractor for an optional body
+///
+/// An empty body gives the default value. Any other body is extracted with [AppJson], so that
+/// a body that is not valid returns an [AppError] instead of being ignored
+pub struct AppJsonOrDefault<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for AppJsonOrDefault<T>
+where
+    T: DeserializeOwned + Default,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (parts, body) = req.into_parts();
+        let bytes = Bytes::from_request(Request::from_parts(parts.clone(), body), state)
+            .await
+            .map_err(|e| AppError::new(ErrorCode::InvalidInput, &e.body_text()))?;
+        if bytes.is_empty() {
+            return Ok(Self(T::default()));
+        }
+        let AppJson(value) =
+            AppJson::<T>::from_request(Request::from_parts(parts, Body::from(bytes)), state)
+                .await?;
+        Ok(Self(value))
+    }
+}
+
+/// Query extractor returning an [AppError] if the query is not valid
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct AppQuery<T>(pub T);
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct InitRequest {
     pub period: VerificationPeriodDef,
 }
 
-#[derive(Deserialize)]
+/// Multipart form of the upload of a dataset, only used for the documentation of the API
+///
+/// The name of the field is [UPLOAD_FIELD_NAME]
+#[derive(ToSchema)]
+pub struct UploadRequest {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct FilePathRequest {
+    #[schema(value_type = String)]
     pub path: PathBuf,
 }
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExtractRequest {
+    #[schema(value_type = String, format = Password)]
+    pub password: SecretString,
+}
+
+/// Request to retry. The password is only used to retry the extraction, and if not given,
+/// the password of the last extraction is used
+#[derive(Deserialize, Default, ToSchema)]
+pub struct RetryRequest {
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = Password)]
+    pub password: Option<SecretString>,
+}
+
+#[derive(Serialize, Deserialize, Default, ToSchema)]
+pub struct RunRequest {
+    #[serde(default)]
+    pub exclusions: Vec<String>,
+    #[serde(default)]
+    pub strategy: RunStrategyDef,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FilesRequest {
+    /// Directory to list. The dataset directories are listed if not given
+    #[param(value_type = Option<String>)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportRequest {
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StateMachineRequest {
+    #[serde(default)]
+    pub format: StateMachineFormat,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extract_request() {
+        use secrecy::ExposeSecret;
+        let req: ExtractRequest = serde_json::from_str(r#"{"password": "secret"}"#).unwrap();
+        assert_eq!(req.password.expose_secret(), "secret");
+        assert!(!format!("{:?}", req.password).contains("secret"));
+        assert!(serde_json::from_str::<ExtractRequest>("{}").is_err());
+    }
+
+    #[test]
+    fn test_run_request() {
+        let req: RunRequest = serde_json::from_str("{}").unwrap();
+        assert!(req.exclusions.is_empty());
+        assert_eq!(req.strategy, RunStrategyDef::Parallel);
+        let req: RunRequest =
+            serde_json::from_str(r#"{"exclusions": ["01.01"], "strategy": "sequential"}"#).unwrap();
+        assert_eq!(req.exclusions, vec!["01.01".to_string()]);
+        assert_eq!(req.strategy, RunStrategyDef::Sequential);
+    }
+}
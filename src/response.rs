@@ -1,32 +1,37 @@
 use std::{collections::HashMap, path::PathBuf};
 
-use crate::app_data::{
-    AppData, AppStatus, InputFileLocation, VerificationInformation, VerificationPeriodDef,
-    VerificationStatus,
+use crate::{
+    app_data::{
+        AppData, AppStatus, InputFileLocation, RunStrategyDef, VerificationInformation,
+        VerificationPeriodDef, VerificationStatus,
+    },
+    error::ErrorCode,
+    state_machine::TransitionEvent,
+    timeline::{durations, progress, timeline, Durations, Progress, TimelineEntry},
 };
-use axum::{http::StatusCode, Json};
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-pub fn response_error_with_status(status: StatusCode, message: &str) -> (StatusCode, Json<String>) {
-    (status, Json(message.to_string()))
-}
-
-pub fn response_anyhow_with_status(
-    status: StatusCode,
-    error: anyhow::Error,
-) -> (StatusCode, Json<String>) {
-    response_error_with_status(status, format!("{:?}", error).as_str())
-}
-
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct StatusResponse {
     pub app_status: AppStatus,
     pub verfification_period: Option<VerificationPeriodDef>,
     pub input_file_location: InputFileLocation,
+    #[schema(value_type = Option<String>)]
     pub location: Option<PathBuf>,
     pub verification_information: HashMap<String, VerificationInformation>,
     pub verification_status: HashMap<String, VerificationStatus>,
+    pub run_strategy: Option<RunStrategyDef>,
     pub error: Option<String>,
+    pub error_code: Option<ErrorCode>,
+    pub warnings: Vec<String>,
+    /// A cancelled extraction or run is still working in the background. No extraction or
+    /// run can be started until its end
+    pub previous_job_finishing: bool,
+    pub progress: Progress,
+    pub durations: Durations,
+    pub timeline: Vec<TimelineEntry>,
 }
 
 impl From<&AppData> for StatusResponse {
@@ -37,13 +42,131 @@ impl From<&AppData> for StatusResponse {
                 .verfification_period
                 .map(|v| VerificationPeriodDef::from(&v)),
             input_file_location: value.input_file_location.clone(),
-            location: value
-                .extracted_dataset_result
-                .as_ref()
-                .map(|r| r.location().to_path_buf()),
+            location: value.extracted_location.clone(),
             verification_information: value.verification_information.clone(),
             verification_status: value.verification_status.clone(),
+            run_strategy: value.run_strategy,
             error: value.error.clone(),
+            error_code: value.error_code,
+            warnings: value.warnings.clone(),
+            previous_job_finishing: value.previous_job_finishing(),
+            progress: progress(value, Local::now()),
+            durations: durations(value),
+            timeline: timeline(value),
         }
     }
 }
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StateMachineResponse {
+    pub initial_status: AppStatus,
+    pub states: Vec<StateMachineStatus>,
+    pub transitions: Vec<StateMachineTransition>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StateMachineStatus {
+    pub status: AppStatus,
+    pub allowed_routes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StateMachineTransition {
+    pub from: AppStatus,
+    pub event: TransitionEvent,
+    pub to: AppStatus,
+    /// Routes triggering the transition. Empty if the transition is internal
+    pub routes: Vec<String>,
+}
+
+/// Kind of an entry of the dataset directories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+pub enum FileKind {
+    Directory,
+    Zip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileEntry {
+    pub name: String,
+    /// Path to send to `/context-dataset` or `/period-dataset`, or to list with `/files`
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    pub kind: FileKind,
+    /// Size in bytes
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FilesResponse {
+    /// Listed directory. `None` for the list of the dataset directories
+    #[schema(value_type = Option<String>)]
+    pub path: Option<PathBuf>,
+    pub entries: Vec<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SessionResponse {
+    pub session_id: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ManualChecksResponse {
+    pub verfification_period: Option<VerificationPeriodDef>,
+    pub direct_trust_fingerprints: HashMap<String, String>,
+    pub election_event: Option<ElectionEventManualChecks>,
+    pub dataset_fingerprints: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ElectionEventManualChecks {
+    pub election_event_id: String,
+    pub election_event_alias: String,
+    pub election_event_description: String,
+    pub start_time: String,
+    pub finish_time: String,
+    pub contest: ContestManualChecks,
+    pub ballot_boxes: Vec<BallotBoxManualChecks>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ContestManualChecks {
+    pub file_date: String,
+    pub voter_total: usize,
+    pub number_of_votes: Option<usize>,
+    pub number_of_election_groups: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BallotBoxManualChecks {
+    pub ballot_box_id: String,
+    pub verification_card_set_id: String,
+    pub verification_card_set_alias: String,
+    pub verification_card_set_description: String,
+    pub test_ballot_box: bool,
+    pub number_of_voting_cards: usize,
+    pub start_time: String,
+    pub finish_time: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerificationsResponse {
+    pub verfification_period: VerificationPeriodDef,
+    /// Categories in the order of the list of verifications
+    pub categories: Vec<VerificationCategoryResponse>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerificationCategoryResponse {
+    pub category: String,
+    pub verifications: Vec<VerificationDescription>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerificationDescription {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+}
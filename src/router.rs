@@ -1,122 +1,327 @@
 use crate::{
-    app_data::{AppDataLockArc, AppStatus},
+    app_data::AppStatus,
+    error::{AppError, ErrorCode},
     handler::{
-        context_dataset_handler, extract_handler, health_check_handler, init_handler,
-        manual_checks_handler, period_dataset_handler, reset_handler, run_handler, status_handler,
+        api_doc, back_handler, cancel_handler, context_dataset_handler,
+        context_dataset_upload_handler, create_session_handler, delete_session_handler,
+        events_handler, extract_handler, files_handler, health_check_handler, init_handler,
+        list_sessions_handler, manual_checks_handler, metrics_handler, period_dataset_handler,
+        period_dataset_upload_handler, report_handler, reset_handler, retry_handler,
+        run_failed_handler, run_handler, run_verification_handler, state_machine_handler,
+        status_handler, verifications_handler, MAX_UPLOAD_SIZE,
     },
+    middlewares::{check_status_middelware, session_middleware},
+    session::Sessions,
 };
 use axum::{
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    http::Uri,
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
-use strum::{AsRefStr, EnumString};
+use strum::{AsRefStr, EnumIter, EnumString};
+use utoipa_swagger_ui::SwaggerUi;
 
 pub const ALLOWED_ROUTE_PATHES: &[(AppStatus, &[RoutePath])] = &[
     (
         AppStatus::NotInitialized,
-        &[RoutePath::Init, RoutePath::Status, RoutePath::Root],
+        &[
+            RoutePath::Init,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Root,
+        ],
     ),
     (
         AppStatus::Initialized,
         &[
             RoutePath::ContextDataset,
+            RoutePath::ContextDatasetUpload,
+            RoutePath::Files,
             RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
             RoutePath::Root,
             RoutePath::Reset,
         ],
     ),
     (
         AppStatus::ContextDataSetLoaded,
-        &[RoutePath::PeriodDataset, RoutePath::Status, RoutePath::Root],
+        &[
+            RoutePath::PeriodDataset,
+            RoutePath::PeriodDatasetUpload,
+            RoutePath::Files,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
+            RoutePath::Root,
+            RoutePath::Back,
+        ],
     ),
     (
         AppStatus::PeriodDataSetLoaded,
         &[
             RoutePath::Extract,
             RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
             RoutePath::Root,
             RoutePath::Reset,
+            RoutePath::Back,
         ],
     ),
     (
         AppStatus::Extracting,
-        &[RoutePath::Status, RoutePath::ManualChecks, RoutePath::Root],
+        &[
+            RoutePath::Cancel,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
+            RoutePath::ManualChecks,
+            RoutePath::Root,
+        ],
     ),
     (
         AppStatus::ExtractError,
-        &[RoutePath::Status, RoutePath::ManualChecks, RoutePath::Root],
+        &[
+            RoutePath::Reset,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
+            RoutePath::ManualChecks,
+            RoutePath::Root,
+            RoutePath::Retry,
+            RoutePath::Back,
+        ],
     ),
     (
         AppStatus::Extracted,
         &[
             RoutePath::Run,
             RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
             RoutePath::ManualChecks,
             RoutePath::Root,
             RoutePath::Reset,
+            RoutePath::Back,
         ],
     ),
     (
         AppStatus::Running,
-        &[RoutePath::Status, RoutePath::ManualChecks, RoutePath::Root],
+        &[
+            RoutePath::Cancel,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
+            RoutePath::ManualChecks,
+            RoutePath::Root,
+        ],
     ),
     (
         AppStatus::RunError,
-        &[RoutePath::Status, RoutePath::ManualChecks, RoutePath::Root],
+        &[
+            RoutePath::Reset,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
+            RoutePath::ManualChecks,
+            RoutePath::Root,
+            RoutePath::Retry,
+            RoutePath::Back,
+        ],
     ),
     (
         AppStatus::Finished,
         &[
+            RoutePath::Report,
             RoutePath::Reset,
             RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
+            RoutePath::ManualChecks,
+            RoutePath::Root,
+            RoutePath::Back,
+            RoutePath::RunFailed,
+            RoutePath::RunVerification,
+        ],
+    ),
+    (
+        AppStatus::Cancelled,
+        &[
+            RoutePath::Reset,
+            RoutePath::Status,
+            RoutePath::Events,
+            RoutePath::StateMachine,
+            RoutePath::Metrics,
+            RoutePath::Verifications,
             RoutePath::ManualChecks,
             RoutePath::Root,
         ],
     ),
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
 pub enum RoutePath {
     #[strum(serialize = "/")]
     Root,
     #[strum(serialize = "/status")]
     Status,
+    #[strum(serialize = "/events")]
+    Events,
+    #[strum(serialize = "/state-machine")]
+    StateMachine,
+    #[strum(serialize = "/metrics")]
+    Metrics,
+    #[strum(serialize = "/verifications")]
+    Verifications,
     #[strum(serialize = "/manual-checks")]
     ManualChecks,
+    #[strum(serialize = "/report")]
+    Report,
     #[strum(serialize = "/init")]
     Init,
+    #[strum(serialize = "/files")]
+    Files,
     #[strum(serialize = "/context-dataset")]
     ContextDataset,
+    #[strum(serialize = "/context-dataset/upload")]
+    ContextDatasetUpload,
     #[strum(serialize = "/period-dataset")]
     PeriodDataset,
+    #[strum(serialize = "/period-dataset/upload")]
+    PeriodDatasetUpload,
     #[strum(serialize = "/extract")]
     Extract,
     #[strum(serialize = "/run")]
     Run,
+    #[strum(serialize = "/run/failed")]
+    RunFailed,
+    #[strum(serialize = "/run/:verification_id")]
+    RunVerification,
+    #[strum(serialize = "/cancel")]
+    Cancel,
+    #[strum(serialize = "/retry")]
+    Retry,
+    #[strum(serialize = "/back")]
+    Back,
     #[strum(serialize = "/reset")]
     Reset,
 }
 
-pub fn routes() -> Router<AppDataLockArc> {
+/// Path of the sessions
+pub const SESSIONS_PATH: &str = "/sessions";
+
+/// Path of a session. The routes of the session are nested in this path
+pub const SESSION_PATH: &str = "/sessions/:session_id";
+
+/// Path of the OpenAPI document
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Path of the Swagger UI page
+pub const DOCS_PATH: &str = "/docs";
+
+/// Routes of a verification, checking the status of the [AppData](crate::app_data::AppData)
+///
+/// The `AppData` must be put in the extensions of the request by a layer
+pub fn routes() -> Router {
     Router::new()
         .route(RoutePath::Root.as_ref(), get(health_check_handler))
         .route(RoutePath::Status.as_ref(), get(status_handler))
+        .route(RoutePath::Events.as_ref(), get(events_handler))
+        .route(RoutePath::StateMachine.as_ref(), get(state_machine_handler))
+        .route(RoutePath::Metrics.as_ref(), get(metrics_handler))
+        .route(
+            RoutePath::Verifications.as_ref(),
+            get(verifications_handler),
+        )
         .route(RoutePath::ManualChecks.as_ref(), get(manual_checks_handler))
+        .route(RoutePath::Report.as_ref(), get(report_handler))
         .route(RoutePath::Init.as_ref(), post(init_handler))
+        .route(RoutePath::Files.as_ref(), get(files_handler))
         .route(
             RoutePath::ContextDataset.as_ref(),
             post(context_dataset_handler),
         )
+        .route(
+            RoutePath::ContextDatasetUpload.as_ref(),
+            post(context_dataset_upload_handler).layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
+        )
         .route(
             RoutePath::PeriodDataset.as_ref(),
             post(period_dataset_handler),
         )
+        .route(
+            RoutePath::PeriodDatasetUpload.as_ref(),
+            post(period_dataset_upload_handler).layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
+        )
         .route(RoutePath::Extract.as_ref(), post(extract_handler))
         .route(RoutePath::Run.as_ref(), post(run_handler))
+        .route(RoutePath::RunFailed.as_ref(), post(run_failed_handler))
+        .route(
+            RoutePath::RunVerification.as_ref(),
+            post(run_verification_handler),
+        )
+        .route(RoutePath::Cancel.as_ref(), post(cancel_handler))
+        .route(RoutePath::Retry.as_ref(), post(retry_handler))
+        .route(RoutePath::Back.as_ref(), post(back_handler))
         .route(RoutePath::Reset.as_ref(), post(reset_handler))
-        .fallback(dummy_handler)
+        .fallback(not_found_handler)
+        .route_layer(middleware::from_fn(check_status_middelware))
+}
+
+/// Routes to manage the sessions, and the routes of each session
+pub fn session_routes(sessions: Sessions) -> Router {
+    Router::new()
+        .route(
+            SESSIONS_PATH,
+            post(create_session_handler).get(list_sessions_handler),
+        )
+        .route(SESSION_PATH, delete(delete_session_handler))
+        .with_state(sessions.clone())
+        .nest(
+            SESSION_PATH,
+            routes().layer(middleware::from_fn_with_state(sessions, session_middleware)),
+        )
+}
+
+/// OpenAPI document and Swagger UI page
+pub fn docs_routes() -> Router {
+    SwaggerUi::new(DOCS_PATH)
+        .url(OPENAPI_PATH, api_doc())
+        .into()
+}
+
+/// Fallback of the unknown routes
+///
+/// The route layers are not applied to the fallback
+pub async fn not_found_handler(uri: Uri) -> AppError {
+    AppError::new(
+        ErrorCode::RouteNotFound,
+        &format!("Path {} not found", uri.path()),
+    )
 }
-pub async fn dummy_handler() {}
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,156 @@
+use crate::app_data::{AppData, AppDataLockArc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Registry of the verification sessions
+///
+/// Each session has its own [AppData]. If a state directory is given, the state of each
+/// session is persisted in the file `<session_id>.json` of the directory, and the sessions
+/// are restored when the registry is created.
+#[derive(Clone, Default)]
+pub struct Sessions {
+    sessions: Arc<RwLock<HashMap<String, AppDataLockArc>>>,
+    state_dir: Option<PathBuf>,
+}
+
+impl Sessions {
+    pub fn new(state_dir: Option<PathBuf>) -> Self {
+        let mut sessions = HashMap::new();
+        if let Some(dir) = state_dir.as_ref() {
+            match std::fs::read_dir(dir) {
+                Ok(entries) => {
+                    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                            continue;
+                        }
+                        if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                            info!("Session {} restored", id);
+                            sessions.insert(id.to_string(), AppData::new_persisted(&path));
+                        }
+                    }
+                }
+                Err(e) => {
+                    if dir.exists() {
+                        warn!("Sessions cannot be restored from {}: {}", dir.display(), e)
+                    }
+                }
+            }
+        }
+        Self {
+            sessions: Arc::new(RwLock::new(sessions)),
+            state_dir,
+        }
+    }
+
+    /// Create a new session and return its id
+    pub async fn create(&self) -> String {
+        let id = Uuid::new_v4().to_string();
+        let app_data = match self.state_dir.as_ref() {
+            Some(dir) => {
+                let app_data = AppData::new_persisted(&dir.join(format!("{}.json", id)));
+                let written = {
+                    let app_data_read = app_data.read().await;
+                    app_data_read.persist();
+                    app_data_read.state_file_written()
+                };
+                if let Some(written) = written {
+                    let _ = written.await;
+                }
+                app_data
+            }
+            None => AppData::new(),
+        };
+        self.sessions.write().await.insert(id.clone(), app_data);
+        info!("Session {} created", id);
+        id
+    }
+
+    pub async fn get(&self, id: &str) -> Option<AppDataLockArc> {
+        self.sessions.read().await.get(id).cloned()
+    }
+
+    /// Ids of the sessions, sorted
+    pub async fn ids(&self) -> Vec<String> {
+        let mut res = self
+            .sessions
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    /// Remove the session, cancelling the running task and deleting the state file and the
+    /// uploaded datasets
+    ///
+    /// Return `false` if the session does not exist
+    pub async fn remove(&self, id: &str) -> bool {
+        let Some(app_data) = self.sessions.write().await.remove(id) else {
+            return false;
+        };
+        let removed = {
+            let mut app_data_mut = app_data.write().await;
+            app_data_mut.cancel();
+            app_data_mut.remove_uploads();
+            app_data_mut.remove_state_file()
+        };
+        if let Some(removed) = removed {
+            let _ = removed.await;
+        }
+        info!("Session {} removed", id);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app_data::AppStatus;
+
+    #[tokio::test]
+    async fn test_sessions() {
+        let sessions = Sessions::default();
+        let id1 = sessions.create().await;
+        let id2 = sessions.create().await;
+        assert_ne!(id1, id2);
+        assert_eq!(sessions.ids().await.len(), 2);
+        sessions.get(&id1).await.unwrap().write().await.app_status = AppStatus::Initialized;
+        assert_eq!(
+            sessions.get(&id2).await.unwrap().read().await.app_status,
+            AppStatus::NotInitialized
+        );
+        // Uploaded dataset of the session
+        let app_data = sessions.get(&id1).await.unwrap();
+        let upload = app_data
+            .read()
+            .await
+            .upload_dir()
+            .join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&upload).unwrap();
+        std::fs::write(upload.join("context-test.zip"), "test").unwrap();
+        app_data.write().await.input_file_location.context_zip_file =
+            Some(upload.join("context-test.zip"));
+        assert!(sessions.remove(&id1).await);
+        assert!(!upload.exists());
+        assert!(!sessions.remove(&id1).await);
+        assert!(sessions.get(&id1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_restore() {
+        let dir = std::env::temp_dir()
+            .join("rust_ev_verifier_gui_backend_test")
+            .join("sessions");
+        let _ = std::fs::remove_dir_all(&dir);
+        let sessions = Sessions::new(Some(dir.clone()));
+        let id = sessions.create().await;
+        let restored = Sessions::new(Some(dir.clone()));
+        assert_eq!(restored.ids().await, vec![id.clone()]);
+        assert!(restored.remove(&id).await);
+        assert!(Sessions::new(Some(dir)).ids().await.is_empty());
+    }
+}
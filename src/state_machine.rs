@@ -0,0 +1,386 @@
+use crate::{
+    app_data::AppStatus,
+    error::{AppError, ErrorCode},
+    router::RoutePath,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+use utoipa::ToSchema;
+
+/// Event triggering a transition of the status of the application
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionEvent {
+    Init,
+    LoadContextDataset,
+    LoadPeriodDataset,
+    StartExtraction,
+    ExtractionSucceeded,
+    ExtractionFailed,
+    StartRun,
+    RunSucceeded,
+    RunFailed,
+    Cancel,
+    /// The backend has been restarted during an extraction or a run
+    Interrupt,
+    /// Restart the failed extraction or run with the same inputs
+    Retry,
+    /// Run again some verifications of a finished run
+    Rerun,
+    /// Go back to the previous step, to change its inputs
+    Back,
+    Reset,
+}
+
+/// Transition from a status to another status, triggered by an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: AppStatus,
+    pub event: TransitionEvent,
+    pub to: AppStatus,
+}
+
+const fn t(from: AppStatus, event: TransitionEvent, to: AppStatus) -> Transition {
+    Transition { from, event, to }
+}
+
+/// Table of the allowed transitions. The initial status is [AppStatus::NotInitialized]
+pub const TRANSITIONS: &[Transition] = &[
+    t(
+        AppStatus::NotInitialized,
+        TransitionEvent::Init,
+        AppStatus::Initialized,
+    ),
+    t(
+        AppStatus::Initialized,
+        TransitionEvent::LoadContextDataset,
+        AppStatus::ContextDataSetLoaded,
+    ),
+    t(
+        AppStatus::ContextDataSetLoaded,
+        TransitionEvent::LoadPeriodDataset,
+        AppStatus::PeriodDataSetLoaded,
+    ),
+    t(
+        AppStatus::PeriodDataSetLoaded,
+        TransitionEvent::StartExtraction,
+        AppStatus::Extracting,
+    ),
+    t(
+        AppStatus::Extracting,
+        TransitionEvent::ExtractionSucceeded,
+        AppStatus::Extracted,
+    ),
+    t(
+        AppStatus::Extracting,
+        TransitionEvent::ExtractionFailed,
+        AppStatus::ExtractError,
+    ),
+    t(
+        AppStatus::Extracting,
+        TransitionEvent::Cancel,
+        AppStatus::Cancelled,
+    ),
+    t(
+        AppStatus::Extracting,
+        TransitionEvent::Interrupt,
+        AppStatus::ExtractError,
+    ),
+    t(
+        AppStatus::Extracted,
+        TransitionEvent::StartRun,
+        AppStatus::Running,
+    ),
+    t(
+        AppStatus::Running,
+        TransitionEvent::RunSucceeded,
+        AppStatus::Finished,
+    ),
+    t(
+        AppStatus::Running,
+        TransitionEvent::RunFailed,
+        AppStatus::RunError,
+    ),
+    t(
+        AppStatus::Running,
+        TransitionEvent::Cancel,
+        AppStatus::Cancelled,
+    ),
+    t(
+        AppStatus::Running,
+        TransitionEvent::Interrupt,
+        AppStatus::RunError,
+    ),
+    t(
+        AppStatus::ExtractError,
+        TransitionEvent::Retry,
+        AppStatus::Extracting,
+    ),
+    t(
+        AppStatus::RunError,
+        TransitionEvent::Retry,
+        AppStatus::Running,
+    ),
+    t(
+        AppStatus::Finished,
+        TransitionEvent::Rerun,
+        AppStatus::Running,
+    ),
+    t(
+        AppStatus::ContextDataSetLoaded,
+        TransitionEvent::Back,
+        AppStatus::Initialized,
+    ),
+    t(
+        AppStatus::PeriodDataSetLoaded,
+        TransitionEvent::Back,
+        AppStatus::ContextDataSetLoaded,
+    ),
+    t(
+        AppStatus::ExtractError,
+        TransitionEvent::Back,
+        AppStatus::PeriodDataSetLoaded,
+    ),
+    t(
+        AppStatus::Extracted,
+        TransitionEvent::Back,
+        AppStatus::PeriodDataSetLoaded,
+    ),
+    t(
+        AppStatus::RunError,
+        TransitionEvent::Back,
+        AppStatus::Extracted,
+    ),
+    t(
+        AppStatus::Finished,
+        TransitionEvent::Back,
+        AppStatus::Extracted,
+    ),
+    t(
+        AppStatus::Initialized,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+    t(
+        AppStatus::PeriodDataSetLoaded,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+    t(
+        AppStatus::ExtractError,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+    t(
+        AppStatus::Extracted,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+    t(
+        AppStatus::RunError,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+    t(
+        AppStatus::Finished,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+    t(
+        AppStatus::Cancelled,
+        TransitionEvent::Reset,
+        AppStatus::NotInitialized,
+    ),
+];
+
+/// Status after the event, or `None` if the transition is not allowed
+pub fn next_status(from: AppStatus, event: TransitionEvent) -> Option<AppStatus> {
+    TRANSITIONS
+        .iter()
+        .find(|t| t.from == from && t.event == event)
+        .map(|t| t.to)
+}
+
+/// Status after the event, or an error [ErrorCode::InvalidStateTransition] if the transition
+/// is not allowed
+pub fn checked_next_status(from: AppStatus, event: TransitionEvent) -> Result<AppStatus, AppError> {
+    next_status(from, event).ok_or_else(|| {
+        AppError::new(
+            ErrorCode::InvalidStateTransition,
+            &format!(
+                "Event {} not allowed for status {}",
+                event.as_ref(),
+                from.as_ref()
+            ),
+        )
+    })
+}
+
+impl TransitionEvent {
+    /// Routes triggering the event. Empty if the event is triggered internally
+    pub fn routes(&self) -> &'static [RoutePath] {
+        match self {
+            TransitionEvent::Init => &[RoutePath::Init],
+            TransitionEvent::LoadContextDataset => {
+                &[RoutePath::ContextDataset, RoutePath::ContextDatasetUpload]
+            }
+            TransitionEvent::LoadPeriodDataset => {
+                &[RoutePath::PeriodDataset, RoutePath::PeriodDatasetUpload]
+            }
+            TransitionEvent::StartExtraction => &[RoutePath::Extract],
+            TransitionEvent::StartRun => &[RoutePath::Run],
+            TransitionEvent::Cancel => &[RoutePath::Cancel],
+            TransitionEvent::Retry => &[RoutePath::Retry],
+            TransitionEvent::Rerun => &[RoutePath::RunFailed, RoutePath::RunVerification],
+            TransitionEvent::Back => &[RoutePath::Back],
+            TransitionEvent::Reset => &[RoutePath::Reset],
+            TransitionEvent::ExtractionSucceeded
+            | TransitionEvent::ExtractionFailed
+            | TransitionEvent::RunSucceeded
+            | TransitionEvent::RunFailed
+            | TransitionEvent::Interrupt => &[],
+        }
+    }
+}
+
+/// Format of the export of the state machine
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, AsRefStr, EnumString, Deserialize, ToSchema,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum StateMachineFormat {
+    #[default]
+    Json,
+    Dot,
+}
+
+/// Export the state machine as Graphviz DOT
+///
+/// The transitions triggered by a route are labelled with the route, the internal transitions
+/// are dashed
+pub fn to_dot() -> String {
+    let mut res = String::from("digraph app_status {\n    rankdir=LR;\n    node [shape=box];\n");
+    // Writing in a String cannot fail
+    let _ = writeln!(
+        res,
+        "    {} [style=bold];",
+        AppStatus::NotInitialized.as_ref()
+    );
+    for status in AppStatus::iter() {
+        if !TRANSITIONS
+            .iter()
+            .any(|t| t.from == status || t.to == status)
+        {
+            let _ = writeln!(res, "    {};", status.as_ref());
+        }
+    }
+    for t in TRANSITIONS {
+        let routes = t.event.routes();
+        let style = match routes.is_empty() {
+            true => ", style=dashed",
+            false => "",
+        };
+        let label = match routes.is_empty() {
+            true => t.event.as_ref().to_string(),
+            false => format!(
+                "{}\\n{}",
+                t.event.as_ref(),
+                routes
+                    .iter()
+                    .map(|r| r.as_ref())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let _ = writeln!(
+            res,
+            "    {} -> {} [label=\"{}\"{}];",
+            t.from.as_ref(),
+            t.to.as_ref(),
+            label,
+            style
+        );
+    }
+    res.push_str("}\n");
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::router::ALLOWED_ROUTE_PATHES;
+
+    #[test]
+    fn test_next_status() {
+        assert_eq!(
+            next_status(AppStatus::NotInitialized, TransitionEvent::Init),
+            Some(AppStatus::Initialized)
+        );
+        assert_eq!(
+            next_status(AppStatus::Finished, TransitionEvent::StartRun),
+            None
+        );
+    }
+
+    #[test]
+    fn test_transitions_unique() {
+        for t in TRANSITIONS {
+            assert_eq!(
+                TRANSITIONS
+                    .iter()
+                    .filter(|o| o.from == t.from && o.event == t.event)
+                    .count(),
+                1,
+                "Transition {:?} not unique",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn test_routes_consistent_with_transitions() {
+        for event in TransitionEvent::iter() {
+            for route in event.routes() {
+                for status in AppStatus::iter() {
+                    let allowed = ALLOWED_ROUTE_PATHES
+                        .iter()
+                        .find(|(s, _)| *s == status)
+                        .map(|(_, ps)| ps.contains(route))
+                        .unwrap_or(false);
+                    assert_eq!(
+                        allowed,
+                        next_status(status, event).is_some(),
+                        "Route {} for status {}",
+                        route.as_ref(),
+                        status.as_ref()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_status_reachable() {
+        for status in AppStatus::iter().filter(|s| *s != AppStatus::NotInitialized) {
+            assert!(
+                TRANSITIONS.iter().any(|t| t.to == status),
+                "{} not reachable",
+                status.as_ref()
+            );
+        }
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = to_dot();
+        assert!(dot.starts_with("digraph app_status {"));
+        assert!(dot.contains("NotInitialized -> Initialized [label=\"init\\n/init\"];"));
+        assert!(dot.contains("RunError -> Running [label=\"retry\\n/retry\"];"));
+        assert!(dot.contains("Running -> Finished [label=\"run_succeeded\", style=dashed];"));
+    }
+}
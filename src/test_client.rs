@@ -0,0 +1,328 @@
+use super::test_helpers::get_data_app;
+use crate::{
+    app_data::{AppData, AppDataEvent, AppStatus, VerificationPeriodDef},
+    app_with_auth, app_with_sessions,
+    auth::{AuthConfig, AuthToken},
+    client::{Client, ClientError},
+    error::ErrorCode,
+    report::ReportFormat,
+    router::DOCS_PATH,
+    session::Sessions,
+    tls::test::write_pem_files,
+};
+use axum::{http::StatusCode, routing::get, Router};
+use axum_server::tls_openssl::OpenSSLAcceptor;
+use futures::StreamExt;
+use secrecy::SecretString;
+use std::{path::Path, time::Duration};
+
+const CONTEXT_FILE_ZIP: &str = "./datasets/Dataset-context-NE_20231124_TT05-20240802_1158.zip";
+const TALLY_FILE_ZIP: &str = "./datasets/Dataset-tally-NE_20231124_TT05-20240802_1207.zip";
+
+/// Maximal duration of the extraction in the tests
+const EXTRACTION_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Serve the application on a free port and return the client
+async fn serve(app: Router) -> Client {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    Client::new(&format!("http://{}", addr))
+}
+
+fn password() -> SecretString {
+    SecretString::from(dotenvy::var("APP_VERIFIER_DATASET_PASSWORD").unwrap())
+}
+
+#[tokio::test]
+async fn test_client_workflow() {
+    let (_, app) = get_data_app();
+    let client = serve(app).await;
+
+    assert_eq!(client.health_check().await.unwrap(), "Server is living");
+    assert_eq!(
+        client.status().await.unwrap().app_status,
+        AppStatus::NotInitialized
+    );
+    let mut events = client.events().await.unwrap();
+    assert!(matches!(
+        events.next().await.unwrap().unwrap(),
+        AppDataEvent::AppStatus {
+            app_status: AppStatus::NotInitialized,
+            ..
+        }
+    ));
+
+    let status = client.init(VerificationPeriodDef::Tally).await.unwrap();
+    assert_eq!(status.app_status, AppStatus::Initialized);
+    assert!(matches!(
+        events.next().await.unwrap().unwrap(),
+        AppDataEvent::AppStatus {
+            app_status: AppStatus::Initialized,
+            ..
+        }
+    ));
+    let status = client
+        .context_dataset_upload(Path::new(CONTEXT_FILE_ZIP))
+        .await
+        .unwrap();
+    assert_eq!(status.app_status, AppStatus::ContextDataSetLoaded);
+    let status = client
+        .period_dataset(Path::new(TALLY_FILE_ZIP))
+        .await
+        .unwrap();
+    assert_eq!(status.app_status, AppStatus::PeriodDataSetLoaded);
+
+    let status = client.extract(&password()).await.unwrap();
+    assert_eq!(status.app_status, AppStatus::Extracting);
+    let status = client
+        .wait_for_status(AppStatus::Extracted, EXTRACTION_TIMEOUT)
+        .await
+        .unwrap();
+    assert!(status.location.is_some());
+    assert!(!client.verifications().await.unwrap().categories.is_empty());
+    client.manual_checks().await.unwrap();
+
+    let status = client.back().await.unwrap();
+    assert_eq!(status.app_status, AppStatus::PeriodDataSetLoaded);
+    let status = client.reset().await.unwrap();
+    assert_eq!(status.app_status, AppStatus::NotInitialized);
+}
+
+#[tokio::test]
+async fn test_client_errors() {
+    let (_, app) = get_data_app();
+    let client = serve(app).await;
+
+    client.init(VerificationPeriodDef::Setup).await.unwrap();
+    let roots = client.files(None).await.unwrap();
+    assert_eq!(roots.entries.len(), 1);
+    let files = client.files(Some(&roots.entries[0].path)).await.unwrap();
+    assert!(files.entries.iter().any(|e| e
+        .path
+        .ends_with(Path::new(CONTEXT_FILE_ZIP).file_name().unwrap())));
+    match client.init(VerificationPeriodDef::Tally).await {
+        Err(ClientError::Api { status, error }) => {
+            assert_eq!(status, StatusCode::CONFLICT);
+            assert_eq!(error.code, ErrorCode::InvalidStateTransition);
+        }
+        _ => panic!("Api error expected"),
+    }
+    match client.report(ReportFormat::Html).await {
+        Err(ClientError::Api { error, .. }) => {
+            assert_eq!(error.code, ErrorCode::InvalidStateTransition)
+        }
+        _ => panic!("Api error expected"),
+    }
+    match client
+        .context_dataset(Path::new("./datasets/toto.zip"))
+        .await
+    {
+        Err(ClientError::Api { error, .. }) => assert_eq!(error.code, ErrorCode::FileNotFound),
+        _ => panic!("Api error expected"),
+    }
+    match client.context_dataset(Path::new("./toto.zip")).await {
+        Err(ClientError::Api { status, error }) => {
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert_eq!(error.code, ErrorCode::PathNotAllowed)
+        }
+        _ => panic!("Api error expected"),
+    }
+    match client.context_dataset_upload(Path::new("./toto.zip")).await {
+        Err(ClientError::Io(_)) => (),
+        _ => panic!("Io error expected"),
+    }
+    // Not extracting or running: the actual status is returned at once
+    match client
+        .wait_for_status(AppStatus::Extracted, Duration::from_secs(5))
+        .await
+    {
+        Err(ClientError::UnexpectedStatus {
+            expected, actual, ..
+        }) => {
+            assert_eq!(expected, AppStatus::Extracted);
+            assert_eq!(actual, AppStatus::Initialized);
+        }
+        _ => panic!("UnexpectedStatus expected"),
+    }
+    assert!(client
+        .state_machine_dot()
+        .await
+        .unwrap()
+        .starts_with("digraph"));
+    assert!(!client.state_machine().await.unwrap().transitions.is_empty());
+    assert!(client
+        .metrics()
+        .await
+        .unwrap()
+        .contains("verifier_app_status"));
+    assert!(matches!(
+        Client::new("http://127.0.0.1:1").status().await,
+        Err(ClientError::Http(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_client_unexpected_response() {
+    let app = Router::new().route(
+        "/status",
+        get(|| async { (StatusCode::IM_A_TEAPOT, "plain") }),
+    );
+    let client = serve(app).await;
+
+    match client.status().await {
+        Err(ClientError::UnexpectedResponse { status, body }) => {
+            assert_eq!(status, StatusCode::IM_A_TEAPOT);
+            assert_eq!(body, "plain");
+        }
+        _ => panic!("UnexpectedResponse expected"),
+    }
+}
+
+#[tokio::test]
+async fn test_client_run_verification_encoded() {
+    let (_, app) = get_data_app();
+    let client = serve(app).await;
+
+    // The id is a single segment: the route is found and rejected by the status
+    match client.run_verification("a/b").await {
+        Err(ClientError::Api { error, .. }) => {
+            assert_eq!(error.code, ErrorCode::InvalidStateTransition)
+        }
+        _ => panic!("Api error expected"),
+    }
+}
+
+#[tokio::test]
+async fn test_client_sessions() {
+    let client = serve(app_with_sessions(AppData::new(), Sessions::default())).await;
+
+    let session_id = client.create_session().await.unwrap().session_id;
+    assert_eq!(
+        client.list_sessions().await.unwrap(),
+        vec![session_id.clone()]
+    );
+    let session = client.session(&session_id);
+    let status = session.init(VerificationPeriodDef::Tally).await.unwrap();
+    assert_eq!(status.app_status, AppStatus::Initialized);
+    assert_eq!(
+        client.status().await.unwrap().app_status,
+        AppStatus::NotInitialized
+    );
+
+    client.delete_session(&session_id).await.unwrap();
+    match session.status().await {
+        Err(ClientError::Api { status, error }) => {
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(error.code, ErrorCode::SessionNotFound);
+        }
+        _ => panic!("Api error expected"),
+    }
+}
+
+#[tokio::test]
+async fn test_client_auth() {
+    let token = AuthToken::generate();
+    let client = serve(app_with_auth(
+        AppData::new(),
+        Sessions::default(),
+        AuthConfig::new(token.clone()),
+    ))
+    .await;
+
+    assert_eq!(client.health_check().await.unwrap(), "Server is living");
+    match client.status().await {
+        Err(ClientError::Api { status, error }) => {
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(error.code, ErrorCode::Unauthorized);
+        }
+        _ => panic!("Api error expected"),
+    }
+    match client
+        .clone()
+        .with_token(AuthToken::generate())
+        .create_session()
+        .await
+    {
+        Err(ClientError::Api { error, .. }) => assert_eq!(error.code, ErrorCode::Unauthorized),
+        _ => panic!("Api error expected"),
+    }
+
+    // The documentation of the API needs the token
+    match client.openapi().await {
+        Err(ClientError::Api { error, .. }) => assert_eq!(error.code, ErrorCode::Unauthorized),
+        _ => panic!("Api error expected"),
+    }
+    let docs = reqwest::get(format!("{}{}/", client.base_url(), DOCS_PATH))
+        .await
+        .unwrap();
+    assert_eq!(docs.status(), StatusCode::UNAUTHORIZED);
+
+    let client = client.with_token(token);
+    assert_eq!(
+        client.status().await.unwrap().app_status,
+        AppStatus::NotInitialized
+    );
+    assert!(client.openapi().await.unwrap()["paths"]["/init"].is_object());
+    let session_id = client.create_session().await.unwrap().session_id;
+    let status = client
+        .session(&session_id)
+        .init(VerificationPeriodDef::Tally)
+        .await
+        .unwrap();
+    assert_eq!(status.app_status, AppStatus::Initialized);
+}
+
+#[tokio::test]
+async fn test_client_public_docs() {
+    let client = serve(app_with_auth(
+        AppData::new(),
+        Sessions::default(),
+        AuthConfig::new(AuthToken::generate()).with_public_docs(true),
+    ))
+    .await;
+
+    assert!(client.openapi().await.unwrap()["paths"]["/init"].is_object());
+    let docs = reqwest::get(format!("{}{}/", client.base_url(), DOCS_PATH))
+        .await
+        .unwrap();
+    assert_eq!(docs.status(), StatusCode::OK);
+    // Only the documentation is open
+    match client.status().await {
+        Err(ClientError::Api { error, .. }) => assert_eq!(error.code, ErrorCode::Unauthorized),
+        _ => panic!("Api error expected"),
+    }
+}
+
+#[tokio::test]
+async fn test_client_tls() {
+    let (dir, cert, tls) = write_pem_files();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = app_with_auth(
+        AppData::new(),
+        Sessions::default(),
+        AuthConfig::new(AuthToken::generate()),
+    );
+    let server = axum_server::from_tcp(listener)
+        .acceptor(OpenSSLAcceptor::new(tls.load().unwrap()))
+        .serve(app.into_make_service());
+    tokio::spawn(async move { server.await.unwrap() });
+
+    let client = Client::new(&format!("https://{}", addr));
+    assert!(matches!(
+        client.health_check().await,
+        Err(ClientError::Http(_))
+    ));
+    let client = client
+        .with_root_certificate(&cert.to_pem().unwrap())
+        .unwrap();
+    assert_eq!(client.health_check().await.unwrap(), "Server is living");
+    assert!(matches!(
+        Client::new(&format!("http://{}", addr))
+            .health_check()
+            .await,
+        Err(ClientError::Http(_))
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -1,14 +1,22 @@
 use super::test_helpers::*;
 use crate::{
-    app_data::{AppStatus, VerificationPeriodDef},
-    response::StatusResponse,
+    app_data::{AppStatus, VerificationPeriodDef, VerificationStatusEnum},
+    error::{ErrorCode, ErrorResponse},
+    fingerprint::sha256_file,
+    response::{
+        FileKind, FilesResponse, ManualChecksResponse, SessionResponse, StateMachineResponse,
+        StatusResponse, VerificationsResponse,
+    },
+    timeline::TimelineEvent,
+    CONFIG,
 };
+use axum::http;
 use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
 use http_body_util::BodyExt;
-use rust_ev_verifier_lib::verification::VerificationPeriod;
+use rust_ev_verifier_lib::verification::{VerificationMetaDataList, VerificationPeriod};
 use std::path::Path;
 use tower::ServiceExt;
 
@@ -46,6 +54,35 @@ async fn test_status() {
     assert_eq!(json.app_status, AppStatus::NotInitialized)
 }
 
+#[tokio::test]
+async fn test_unknown_route() {
+    let (_, app) = get_data_app();
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/toto").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    is_response_json(&response);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::RouteNotFound);
+
+    // Unknown route in a session
+    let response = call_create_session(&app).await;
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let session_id = serde_json::from_slice::<SessionResponse>(&body)
+        .unwrap()
+        .session_id;
+    let response = call_in_session(&app, &session_id, http::Method::GET, "/toto", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::RouteNotFound);
+    call_delete_session(&app, &session_id).await;
+}
+
 #[tokio::test]
 async fn test_init() {
     let (data, app) = get_data_app();
@@ -70,6 +107,37 @@ async fn test_init() {
     );
 }
 
+async fn next_event(body: &mut Body) -> String {
+    let frame = body.frame().await.unwrap().unwrap();
+    String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_events() {
+    let (_, app) = get_data_app();
+
+    let response = call_events(&app).await;
+
+    is_response_ok(&response);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        mime::TEXT_EVENT_STREAM.as_ref()
+    );
+    let mut body = response.into_body();
+    let event = next_event(&mut body).await;
+    assert!(event.contains("event: app_status"));
+    assert!(event.contains("\"NotInitialized\""));
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let event = next_event(&mut body).await;
+    assert!(event.contains("event: app_status"));
+    assert!(event.contains("\"Initialized\""));
+
+    let _ = call_reset(&app).await;
+    let event = next_event(&mut body).await;
+    assert!(event.contains("\"NotInitialized\""));
+}
+
 #[tokio::test]
 async fn test_reset() {
     let (data, app) = get_data_app();
@@ -99,25 +167,14 @@ async fn test_files_setup() {
 
     let read_data = data.read().await;
     assert_eq!(read_data.app_status, AppStatus::PeriodDataSetLoaded);
+    // The canonical path is stored
     assert_eq!(
-        read_data
-            .input_file_location
-            .context_zip_file
-            .as_ref()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-        CONTEXT_FILE_ZIP
+        read_data.input_file_location.context_zip_file,
+        Some(Path::new(CONTEXT_FILE_ZIP).canonicalize().unwrap())
     );
     assert_eq!(
-        read_data
-            .input_file_location
-            .setup_zip_file
-            .as_ref()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-        SETUP_FILE_ZIP
+        read_data.input_file_location.setup_zip_file,
+        Some(Path::new(SETUP_FILE_ZIP).canonicalize().unwrap())
     );
     assert!(read_data.input_file_location.tally_zip_file.is_none())
 }
@@ -136,27 +193,127 @@ async fn test_files_tally() {
 
     let read_data = data.read().await;
     assert_eq!(read_data.app_status, AppStatus::PeriodDataSetLoaded);
+    // The canonical path is stored
     assert_eq!(
-        read_data
-            .input_file_location
-            .context_zip_file
-            .as_ref()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-        CONTEXT_FILE_ZIP
+        read_data.input_file_location.context_zip_file,
+        Some(Path::new(CONTEXT_FILE_ZIP).canonicalize().unwrap())
+    );
+    assert_eq!(
+        read_data.input_file_location.tally_zip_file,
+        Some(Path::new(TALLY_FILE_ZIP).canonicalize().unwrap())
+    );
+    assert!(read_data.input_file_location.setup_zip_file.is_none());
+    assert_eq!(
+        read_data.input_file_location.context_sha256,
+        Some(sha256_file(Path::new(CONTEXT_FILE_ZIP)).unwrap())
     );
     assert_eq!(
         read_data
             .input_file_location
-            .tally_zip_file
+            .tally_sha256
             .as_ref()
             .unwrap()
-            .to_str()
-            .unwrap(),
-        TALLY_FILE_ZIP
+            .len(),
+        64
     );
-    assert!(read_data.input_file_location.setup_zip_file.is_none())
+    assert!(read_data.input_file_location.setup_sha256.is_none());
+}
+
+#[tokio::test]
+async fn test_files_upload() {
+    let (data, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let response =
+        call_upload_file(&app, Path::new(CONTEXT_FILE_ZIP), "/context-dataset/upload").await;
+    is_response_ok(&response);
+    let response =
+        call_upload_file(&app, Path::new(TALLY_FILE_ZIP), "/period-dataset/upload").await;
+    is_response_ok(&response);
+
+    let read_data = data.read().await;
+    assert_eq!(read_data.app_status, AppStatus::PeriodDataSetLoaded);
+    let context = read_data
+        .input_file_location
+        .context_zip_file
+        .as_ref()
+        .unwrap();
+    assert!(context.ends_with("context-Dataset-context-NE_20231124_TT05-20240802_1158.zip"));
+    assert_eq!(
+        std::fs::read(context).unwrap(),
+        std::fs::read(CONTEXT_FILE_ZIP).unwrap()
+    );
+    let tally = read_data
+        .input_file_location
+        .tally_zip_file
+        .as_ref()
+        .unwrap();
+    assert!(tally.ends_with("tally-Dataset-tally-NE_20231124_TT05-20240802_1207.zip"));
+    assert_eq!(
+        std::fs::read(tally).unwrap(),
+        std::fs::read(TALLY_FILE_ZIP).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_files_upload_deleted() {
+    let (data, app) = get_data_app();
+    let upload_dir =
+        |path: &Option<std::path::PathBuf>| path.as_ref().unwrap().parent().unwrap().to_path_buf();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let _ = call_upload_file(&app, Path::new(CONTEXT_FILE_ZIP), "/context-dataset/upload").await;
+    let context_dir = upload_dir(&data.read().await.input_file_location.context_zip_file);
+    assert!(context_dir.exists());
+    let response = call_back(&app).await;
+    is_response_ok(&response);
+    assert!(!context_dir.exists());
+
+    let _ = call_upload_file(&app, Path::new(CONTEXT_FILE_ZIP), "/context-dataset/upload").await;
+    let _ = call_upload_file(&app, Path::new(TALLY_FILE_ZIP), "/period-dataset/upload").await;
+    let (context_dir, tally_dir) = {
+        let read_data = data.read().await;
+        assert_eq!(read_data.app_status, AppStatus::PeriodDataSetLoaded);
+        (
+            upload_dir(&read_data.input_file_location.context_zip_file),
+            upload_dir(&read_data.input_file_location.tally_zip_file),
+        )
+    };
+    assert!(context_dir.exists() && tally_dir.exists());
+    let response = call_reset(&app).await;
+    is_response_ok(&response);
+    assert!(!context_dir.exists());
+    assert!(!tally_dir.exists());
+
+    // Upload interrupted: the multipart body is not terminated
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let file_name = format!("{}.zip", uuid::Uuid::new_v4());
+    let body = format!(
+        "--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\nabc",
+        file_name
+    );
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/context-dataset/upload")
+                .header(
+                    http::header::CONTENT_TYPE,
+                    "multipart/form-data; boundary=b",
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let uploads = data.read().await.upload_dir();
+    let file_name = format!("context-{}", file_name);
+    assert!(std::fs::read_dir(uploads)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .all(|e| !e.path().join(&file_name).exists()));
 }
 
 #[tokio::test]
@@ -164,14 +321,73 @@ async fn test_files_error() {
     let (_, app) = get_data_app();
 
     let _ = call_init(&app, VerificationPeriodDef::Tally).await;
-    let response = call_input_context(&app, Path::new("./toto")).await;
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let response = call_input_context(&app, Path::new("./datasets/toto")).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::FileNotFound);
+
+    // Outside the dataset directories
+    for path in ["./toto", "./Cargo.toml", "./datasets/../Cargo.toml"] {
+        let response = call_input_context(&app, Path::new(path)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.code, ErrorCode::PathNotAllowed);
+    }
 
-    let _ = call_reset(&app);
+    let _ = call_reset(&app).await;
     let _ = call_init(&app, VerificationPeriodDef::Tally).await;
     let _ = call_input_context(&app, Path::new(CONTEXT_FILE_ZIP)).await;
-    let response = call_input_period_dataset(&app, Path::new("./toto")).await;
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let response = call_input_period_dataset(&app, Path::new("./datasets/toto")).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = call_input_period_dataset(&app, Path::new("./Cargo.toml")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_list_files() {
+    let (_, app) = get_data_app();
+
+    let response = call_files(&app, None).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let response = call_files(&app, None).await;
+    is_response_ok(&response);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: FilesResponse = serde_json::from_slice(&body).unwrap();
+    assert!(json.path.is_none());
+    assert_eq!(json.entries.len(), 1);
+    assert_eq!(json.entries[0].kind, FileKind::Directory);
+    assert_eq!(
+        json.entries[0].path,
+        Path::new("./datasets").canonicalize().unwrap()
+    );
+
+    let response = call_files(&app, Some("./datasets")).await;
+    is_response_ok(&response);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: FilesResponse = serde_json::from_slice(&body).unwrap();
+    assert!(json.entries.iter().all(|e| e.kind == FileKind::Zip));
+    let context = json
+        .entries
+        .iter()
+        .find(|e| e.path == Path::new(CONTEXT_FILE_ZIP).canonicalize().unwrap())
+        .unwrap();
+    assert_eq!(
+        context.size,
+        std::fs::metadata(CONTEXT_FILE_ZIP).unwrap().len()
+    );
+
+    // The path of an entry is accepted by the dataset route
+    let response = call_input_context(&app, &context.path).await;
+    is_response_ok(&response);
+
+    let response = call_files(&app, Some("./datasets/..")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = call_files(&app, Some("./datasets/toto")).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
@@ -191,7 +407,7 @@ async fn test_extract() {
     }
 
     loop {
-        let _ = tokio::time::sleep(tokio::time::Duration::from_millis(1000));
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
         let _ = call_status(&app).await;
         {
             let read_data = data.read().await;
@@ -203,4 +419,588 @@ async fn test_extract() {
 
     let read_data = data.read().await;
     assert!(read_data.extracted_dataset_result.is_some());
+    assert!(read_data.extracted_location.is_some());
+}
+
+#[tokio::test]
+async fn test_manual_checks() {
+    let (data, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let _ = call_input_context(&app, Path::new(CONTEXT_FILE_ZIP)).await;
+    let _ = call_input_period_dataset(&app, Path::new(TALLY_FILE_ZIP)).await;
+    let _ = call_extract(&app).await;
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        if data.read().await.app_status == AppStatus::Extracted {
+            break;
+        }
+    }
+
+    let response = call_status(&app).await;
+    let json: StatusResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert!(json.durations.extraction_ms.is_some());
+    assert_eq!(
+        json.timeline.iter().map(|e| e.event).collect::<Vec<_>>(),
+        vec![
+            TimelineEvent::ExtractionStarted,
+            TimelineEvent::ExtractionFinished
+        ]
+    );
+
+    let response = call_manual_checks(&app).await;
+    is_response_ok(&response);
+    is_response_json(&response);
+    let json: ManualChecksResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(
+        json.verfification_period,
+        Some(VerificationPeriodDef::Tally)
+    );
+    assert!(!json.direct_trust_fingerprints.is_empty());
+    assert!(json.election_event.is_some());
+    assert!(json.dataset_fingerprints.contains_key("context"));
+    assert!(json.dataset_fingerprints.contains_key("tally"));
+    assert!(!json.dataset_fingerprints.contains_key("setup"));
+}
+
+async fn wait_status_after_extracting(data: &crate::app_data::AppDataLockArc) -> AppStatus {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let status = data.read().await.app_status;
+        if status != AppStatus::Extracting {
+            return status;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_retry_back() {
+    let (data, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let _ = call_input_context(&app, Path::new(CONTEXT_FILE_ZIP)).await;
+    let _ = call_input_period_dataset(&app, Path::new(TALLY_FILE_ZIP)).await;
+
+    let response = call_with_password(&app, "/retry", "wrong").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let _ = call_with_password(&app, "/extract", "wrong").await;
+    assert_eq!(
+        wait_status_after_extracting(&data).await,
+        AppStatus::ExtractError
+    );
+    assert_eq!(
+        data.read().await.error_code,
+        Some(ErrorCode::ExtractionFailed)
+    );
+
+    // A body that is not valid is not replaced by the stored password
+    for body in ["{\"password\": 1}", "{\"password\": "] {
+        let response = call_with_body(&app, "/retry", mime::APPLICATION_JSON.as_ref(), body).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            body
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.code, ErrorCode::InvalidInput);
+        assert_eq!(data.read().await.app_status, AppStatus::ExtractError);
+    }
+
+    let password = dotenvy::var("APP_VERIFIER_DATASET_PASSWORD").unwrap();
+    let response = call_with_password(&app, "/retry", &password).await;
+    is_response_ok(&response);
+    let json: StatusResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(json.app_status, AppStatus::Extracting);
+    assert!(json.error.is_none());
+    assert_eq!(
+        wait_status_after_extracting(&data).await,
+        AppStatus::Extracted
+    );
+
+    let response = call_back(&app).await;
+    is_response_ok(&response);
+    {
+        let read_data = data.read().await;
+        assert_eq!(read_data.app_status, AppStatus::PeriodDataSetLoaded);
+        assert!(read_data.extracted_location.is_none());
+        assert!(read_data.dataset_password.is_none());
+        assert!(read_data.input_file_location.tally_zip_file.is_some());
+    }
+
+    let response = call_back(&app).await;
+    is_response_ok(&response);
+    {
+        let read_data = data.read().await;
+        assert_eq!(read_data.app_status, AppStatus::ContextDataSetLoaded);
+        assert!(read_data.input_file_location.tally_zip_file.is_none());
+    }
+    let response = call_input_period_dataset(&app, Path::new(TALLY_FILE_ZIP)).await;
+    is_response_ok(&response);
+}
+
+#[tokio::test]
+async fn test_verifications() {
+    let (_, app) = get_data_app();
+
+    let response = call_verifications(&app).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let _ = call_init(&app, VerificationPeriodDef::Setup).await;
+    let response = call_verifications(&app).await;
+    is_response_ok(&response);
+    is_response_json(&response);
+    let json: VerificationsResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(json.verfification_period, VerificationPeriodDef::Setup);
+    let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+    assert_eq!(
+        json.categories
+            .iter()
+            .map(|c| c.verifications.len())
+            .sum::<usize>(),
+        metadata
+            .id_list_for_period(&VerificationPeriod::Setup)
+            .len()
+    );
+    assert!(json
+        .categories
+        .iter()
+        .flat_map(|c| c.verifications.iter())
+        .all(|v| !v.name.is_empty()));
+}
+
+#[tokio::test]
+async fn test_manual_checks_not_allowed() {
+    let (_, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let response = call_manual_checks(&app).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::InvalidStateTransition);
+}
+
+#[tokio::test]
+async fn test_report_not_allowed() {
+    let (_, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let response = call_report(&app, "html").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_report() {
+    let (state, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Setup).await;
+    {
+        let mut state_mut = state.write().await;
+        let metadata =
+            VerificationMetaDataList::load(state_mut.config.get_verification_list_str()).unwrap();
+        state_mut.set_with_medata(&metadata, &[]);
+        let ids = state_mut
+            .verification_status
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for id in ids {
+            state_mut.set_verification_status(&id, vec![], vec![], chrono::Local::now());
+        }
+        state_mut.app_status = AppStatus::Finished;
+    }
+
+    let response = call_report(&app, "html").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        mime::TEXT_HTML_UTF_8.as_ref()
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+    assert!(html.contains("Verification report"));
+    assert!(html.contains("Successful"));
+
+    let response = call_report(&app, "pdf").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        mime::APPLICATION_PDF.as_ref()
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.starts_with(b"%PDF"));
+
+    let response = call_report(&app, "docx").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::InvalidInput);
+}
+
+#[tokio::test]
+async fn test_run_invalid_body() {
+    let (state, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    {
+        let mut state_mut = state.write().await;
+        state_mut.extracted_location = Some(Path::new("./toto").to_path_buf());
+        state_mut.app_status = AppStatus::Extracted;
+    }
+
+    for (content_type, body) in [
+        (
+            mime::APPLICATION_JSON.as_ref(),
+            "{\"strategy\": \"serial\"}",
+        ),
+        (
+            mime::APPLICATION_JSON.as_ref(),
+            "{\"exclusions\": \"10.01\"}",
+        ),
+        (mime::APPLICATION_JSON.as_ref(), "{\"exclusions\": "),
+        (mime::TEXT_PLAIN.as_ref(), "{\"exclusions\": [\"10.01\"]}"),
+    ] {
+        let response = call_with_body(&app, "/run", content_type, body).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            body
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.code, ErrorCode::InvalidInput);
+        // Nothing started
+        let read_data = state.read().await;
+        assert_eq!(read_data.app_status, AppStatus::Extracted);
+        assert!(read_data.verification_status.is_empty());
+        assert!(read_data.run_started_at.is_none());
+    }
+
+    // An empty body runs all the verifications
+    let response = call_with_body(&app, "/run", mime::APPLICATION_JSON.as_ref(), "").await;
+    is_response_ok(&response);
+    assert!(!state.read().await.verification_status.is_empty());
+}
+
+#[tokio::test]
+async fn test_run_again() {
+    let (state, app) = get_data_app();
+
+    let response = call_run_again(&app, "failed").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let _ = call_init(&app, VerificationPeriodDef::Setup).await;
+    let ids = {
+        let mut state_mut = state.write().await;
+        let metadata =
+            VerificationMetaDataList::load(state_mut.config.get_verification_list_str()).unwrap();
+        state_mut.set_with_medata(&metadata, &[]);
+        let mut ids = state_mut
+            .verification_status
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        ids.sort();
+        for id in ids.iter() {
+            state_mut.set_verification_status(id, vec![], vec![], chrono::Local::now());
+        }
+        state_mut.extracted_location = Some(Path::new("./toto").to_path_buf());
+        state_mut.app_status = AppStatus::Finished;
+        ids
+    };
+
+    let response = call_run_again(&app, "failed").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = call_run_again(&app, "99.99").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::VerificationNotFound);
+
+    let response = call_run_again(&app, &ids[0]).await;
+    is_response_ok(&response);
+    let json: StatusResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(json.app_status, AppStatus::Running);
+    {
+        let read_data = state.read().await;
+        assert_eq!(
+            read_data.verification_status.get(&ids[0]).unwrap().status,
+            VerificationStatusEnum::NotStarted
+        );
+        assert_eq!(
+            read_data.verification_status.get(&ids[1]).unwrap().status,
+            VerificationStatusEnum::FinishedSuccessfully
+        );
+    }
+
+    // The extracted location does not exist
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        if state.read().await.app_status != AppStatus::Running {
+            break;
+        }
+    }
+    assert_eq!(state.read().await.app_status, AppStatus::RunError);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cancel_extract() {
+    let (data, app) = get_data_app();
+
+    let response = call_cancel(&app).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let _ = call_input_context(&app, Path::new(CONTEXT_FILE_ZIP)).await;
+    let _ = call_input_period_dataset(&app, Path::new(TALLY_FILE_ZIP)).await;
+    let _ = call_extract(&app).await;
+
+    let response = call_cancel(&app).await;
+
+    is_response_ok(&response);
+    is_response_json(&response);
+    let json: StatusResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(json.app_status, AppStatus::Cancelled);
+    {
+        let read_data = data.read().await;
+        assert_eq!(read_data.app_status, AppStatus::Cancelled);
+        assert!(read_data.task_handle.is_none());
+    }
+
+    let response = call_reset(&app).await;
+    is_response_ok(&response);
+    let read_data = data.read().await;
+    assert_eq!(read_data.app_status, AppStatus::NotInitialized);
+}
+
+#[tokio::test]
+async fn test_extract_previous_job_finishing() {
+    let (data, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    let _ = call_input_context(&app, Path::new(CONTEXT_FILE_ZIP)).await;
+    let _ = call_input_period_dataset(&app, Path::new(TALLY_FILE_ZIP)).await;
+    // Blocking thread of a cancelled extraction
+    let job = data.read().await.background_jobs.start();
+
+    let response = call_extract(&app).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let json: ErrorResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(json.code, ErrorCode::PreviousJobFinishing);
+    let response = call_status(&app).await;
+    let json: StatusResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(json.app_status, AppStatus::PeriodDataSetLoaded);
+    assert!(json.previous_job_finishing);
+
+    drop(job);
+    let response = call_status(&app).await;
+    let json: StatusResponse =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert!(!json.previous_job_finishing);
+    let response = call_extract(&app).await;
+    is_response_ok(&response);
+    let _ = call_cancel(&app).await;
+}
+
+#[tokio::test]
+async fn test_sessions() {
+    let (_, app) = get_data_app();
+
+    let response = call_create_session(&app).await;
+    is_response_ok(&response);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let session_id = serde_json::from_slice::<SessionResponse>(&body)
+        .unwrap()
+        .session_id;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/sessions")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let ids: Vec<String> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(ids, vec![session_id.clone()]);
+
+    // Init in the session
+    let response = call_in_session(
+        &app,
+        &session_id,
+        http::Method::POST,
+        "/init",
+        Some("{\"period\": \"tally\"}".to_string()),
+    )
+    .await;
+    is_response_ok(&response);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: StatusResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.app_status, AppStatus::Initialized);
+
+    // The state machine of the session is checked
+    let response = call_in_session(
+        &app,
+        &session_id,
+        http::Method::POST,
+        "/init",
+        Some("{\"period\": \"tally\"}".to_string()),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = call_in_session(&app, &session_id, http::Method::POST, "/run/01.01", None).await;
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::InvalidStateTransition);
+
+    // The default verification is not affected
+    let response = call_status(&app).await;
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: StatusResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.app_status, AppStatus::NotInitialized);
+    let response = call_init(&app, VerificationPeriodDef::Setup).await;
+    is_response_ok(&response);
+
+    // Delete the session
+    let response = call_delete_session(&app, &session_id).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = call_in_session(&app, &session_id, http::Method::GET, "/status", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.code, ErrorCode::SessionNotFound);
+    let response = call_delete_session(&app, &session_id).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_metrics() {
+    let (_, app) = get_data_app();
+
+    let _ = call_status(&app).await;
+    let _ = call_run_again(&app, "01.01").await;
+    let _ = app
+        .clone()
+        .oneshot(Request::builder().uri("/toto").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let response = call_metrics(&app).await;
+    is_response_ok(&response);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        prometheus::TEXT_FORMAT
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text
+        .contains("verifier_http_requests_total{method=\"GET\",route=\"/status\",status=\"200\"}"));
+    assert!(text.contains(
+        "verifier_http_requests_total{method=\"POST\",route=\"/run/:verification_id\",status=\"409\"}"
+    ));
+    assert!(text.contains(
+        "verifier_http_requests_total{method=\"GET\",route=\"unmatched\",status=\"404\"}"
+    ));
+    assert!(text.contains("verifier_app_status{status=\"NotInitialized\"} 1"));
+    assert!(text.contains("verifier_verifications{status=\"Running\"} 0"));
+}
+
+/// Number of observations of the histogram `name` longer than one hour
+///
+/// The durations of the tests are shorter, so that the other tests running in parallel don't
+/// change the number
+async fn long_observations(app: &axum::Router, name: &str) -> u64 {
+    let response = call_metrics(app).await;
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    let value = |metric: String| -> u64 {
+        text.lines()
+            .find_map(|l| l.strip_prefix(&format!("{} ", metric)))
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    value(format!("{}_count", name)) - value(format!("{}_bucket{{le=\"3600\"}}", name))
+}
+
+#[tokio::test]
+async fn test_metrics_back() {
+    let (data, app) = get_data_app();
+
+    let _ = call_init(&app, VerificationPeriodDef::Tally).await;
+    {
+        let mut data_mut = data.write().await;
+        let now = chrono::Local::now();
+        data_mut.extracted_location = Some(Path::new("./toto").to_path_buf());
+        data_mut.extraction_started_at = Some(now - chrono::TimeDelta::hours(2));
+        data_mut.extraction_finished_at = Some(now);
+        data_mut.run_started_at = Some(now - chrono::TimeDelta::hours(2));
+        data_mut.run_finished_at = Some(now);
+        data_mut.app_status = AppStatus::Finished;
+    }
+    let extraction = long_observations(&app, "verifier_extraction_duration_seconds").await;
+    let run = long_observations(&app, "verifier_run_duration_seconds").await;
+
+    // Finished -> Extracted -> PeriodDataSetLoaded
+    is_response_ok(&call_back(&app).await);
+    is_response_ok(&call_back(&app).await);
+    assert_eq!(data.read().await.app_status, AppStatus::PeriodDataSetLoaded);
+    assert_eq!(
+        long_observations(&app, "verifier_extraction_duration_seconds").await,
+        extraction
+    );
+    assert_eq!(
+        long_observations(&app, "verifier_run_duration_seconds").await,
+        run
+    );
+}
+
+#[tokio::test]
+async fn test_state_machine() {
+    let (_, app) = get_data_app();
+
+    let response = call_state_machine(&app, "json").await;
+    is_response_ok(&response);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: StateMachineResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json.initial_status, AppStatus::NotInitialized);
+    assert!(json
+        .transitions
+        .iter()
+        .any(|t| t.from == AppStatus::NotInitialized
+            && t.to == AppStatus::Initialized
+            && t.routes == vec!["/init".to_string()]));
+    assert!(json
+        .states
+        .iter()
+        .find(|s| s.status == AppStatus::NotInitialized)
+        .unwrap()
+        .allowed_routes
+        .contains(&"/init".to_string()));
+
+    let _ = call_init(&app, VerificationPeriodDef::Setup).await;
+    let response = call_state_machine(&app, "dot").await;
+    is_response_ok(&response);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        "text/vnd.graphviz"
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(String::from_utf8(body.to_vec())
+        .unwrap()
+        .starts_with("digraph"));
 }
@@ -0,0 +1,234 @@
+use crate::app_data::{AppData, AppStatus, VerificationStatusEnum};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Event of the timeline, in the order of the steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEvent {
+    ExtractionStarted,
+    ExtractionFinished,
+    RunStarted,
+    VerificationStarted,
+    VerificationFinished,
+    RunFinished,
+}
+
+/// Entry of the timeline. The id is only set for the events of a verification
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TimelineEntry {
+    pub at: DateTime<Local>,
+    pub event: TimelineEvent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_id: Option<String>,
+}
+
+/// Durations in milliseconds. Only the finished steps have a duration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Durations {
+    pub extraction_ms: Option<i64>,
+    pub run_ms: Option<i64>,
+    pub verifications_ms: HashMap<String, i64>,
+}
+
+/// Progress of the run
+///
+/// The excluded verifications are not counted. The estimated time of arrival is only
+/// calculated during the run, with the mean duration of the verifications finished in the run
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Progress {
+    pub total: usize,
+    pub finished: usize,
+    pub percentage: f64,
+    pub eta: Option<DateTime<Local>>,
+}
+
+fn duration_ms(start: Option<DateTime<Local>>, end: Option<DateTime<Local>>) -> Option<i64> {
+    Some((end? - start?).num_milliseconds())
+}
+
+/// Chronological timeline of the extraction and of the run
+pub fn timeline(data: &AppData) -> Vec<TimelineEntry> {
+    let entry = |at: Option<DateTime<Local>>, event: TimelineEvent, id: Option<&String>| {
+        at.map(|at| TimelineEntry {
+            at,
+            event,
+            verification_id: id.cloned(),
+        })
+    };
+    let mut res = vec![
+        entry(
+            data.extraction_started_at,
+            TimelineEvent::ExtractionStarted,
+            None,
+        ),
+        entry(
+            data.extraction_finished_at,
+            TimelineEvent::ExtractionFinished,
+            None,
+        ),
+        entry(data.run_started_at, TimelineEvent::RunStarted, None),
+        entry(data.run_finished_at, TimelineEvent::RunFinished, None),
+    ];
+    for vs in data.verification_status.values() {
+        res.push(entry(
+            vs.started_at,
+            TimelineEvent::VerificationStarted,
+            Some(&vs.id),
+        ));
+        res.push(entry(
+            vs.finished_at,
+            TimelineEvent::VerificationFinished,
+            Some(&vs.id),
+        ));
+    }
+    let mut res = res.into_iter().flatten().collect::<Vec<_>>();
+    // The events at the same time are ordered by kind (e.g. start before finish), then by id
+    res.sort_by(|a, b| {
+        a.at.cmp(&b.at)
+            .then(a.event.cmp(&b.event))
+            .then(a.verification_id.cmp(&b.verification_id))
+    });
+    res
+}
+
+/// Durations of the extraction, of the run and of each finished verification
+pub fn durations(data: &AppData) -> Durations {
+    Durations {
+        extraction_ms: duration_ms(data.extraction_started_at, data.extraction_finished_at),
+        run_ms: duration_ms(data.run_started_at, data.run_finished_at),
+        verifications_ms: data
+            .verification_status
+            .values()
+            .filter_map(|vs| duration_ms(vs.started_at, vs.finished_at).map(|d| (vs.id.clone(), d)))
+            .collect(),
+    }
+}
+
+/// Progress of the run at the given time
+pub fn progress(data: &AppData, now: DateTime<Local>) -> Progress {
+    let counted = data
+        .verification_status
+        .values()
+        .filter(|vs| vs.status != VerificationStatusEnum::Excluded)
+        .collect::<Vec<_>>();
+    let total = counted.len();
+    let finished = counted
+        .iter()
+        .filter(|vs| {
+            vs.status != VerificationStatusEnum::NotStarted
+                && vs.status != VerificationStatusEnum::Running
+        })
+        .count();
+    let percentage = match total {
+        0 => match data.app_status {
+            AppStatus::Finished => 100.0,
+            _ => 0.0,
+        },
+        _ => (finished as f64 * 1000.0 / total as f64).round() / 10.0,
+    };
+    let eta = match (data.app_status, data.run_started_at) {
+        (AppStatus::Running, Some(started)) if finished < total => {
+            // Only the verifications finished in this run (e.g. not before a run again)
+            let finished_in_run = counted
+                .iter()
+                .filter(|vs| vs.finished_at.is_some_and(|f| f >= started))
+                .count() as i32;
+            match finished_in_run {
+                0 => None,
+                n => Some(now + (now - started) / n * (total - finished) as i32),
+            }
+        }
+        _ => None,
+    };
+    Progress {
+        total,
+        finished,
+        percentage,
+        eta,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CONFIG;
+    use chrono::TimeDelta;
+    use rust_ev_verifier_lib::verification::{VerificationMetaDataList, VerificationPeriod};
+
+    fn running_data() -> (AppData, Vec<String>, DateTime<Local>) {
+        let metadata = VerificationMetaDataList::load(CONFIG.get_verification_list_str()).unwrap();
+        let mut data = AppData::default();
+        data.verfification_period = Some(VerificationPeriod::Setup);
+        let mut ids = metadata
+            .id_list_for_period(&VerificationPeriod::Setup)
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        data.set_with_medata(&metadata, std::slice::from_ref(&ids[3]));
+        let start = Local::now() - TimeDelta::seconds(20);
+        data.extraction_started_at = Some(start - TimeDelta::seconds(60));
+        data.extraction_finished_at = Some(start - TimeDelta::seconds(30));
+        data.run_started_at = Some(start);
+        data.app_status = AppStatus::Running;
+        for (i, id) in ids.iter().take(2).enumerate() {
+            let vs = data.verification_status.get_mut(id).unwrap();
+            vs.status = VerificationStatusEnum::FinishedSuccessfully;
+            vs.started_at = Some(start + TimeDelta::seconds(i as i64));
+            vs.finished_at = Some(start + TimeDelta::seconds(i as i64 + 5));
+        }
+        let vs = data.verification_status.get_mut(&ids[2]).unwrap();
+        vs.status = VerificationStatusEnum::Running;
+        vs.started_at = Some(start + TimeDelta::seconds(10));
+        (data, ids, start)
+    }
+
+    #[test]
+    fn test_timeline() {
+        let (data, ids, start) = running_data();
+        let timeline = timeline(&data);
+        assert_eq!(timeline.len(), 8);
+        assert_eq!(timeline[0].event, TimelineEvent::ExtractionStarted);
+        assert_eq!(timeline[2].event, TimelineEvent::RunStarted);
+        assert_eq!(timeline[2].at, start);
+        assert_eq!(timeline[3].event, TimelineEvent::VerificationStarted);
+        assert_eq!(timeline[3].verification_id, Some(ids[0].clone()));
+        assert!(timeline.windows(2).all(|w| w[0].at <= w[1].at));
+    }
+
+    #[test]
+    fn test_durations() {
+        let (data, ids, _) = running_data();
+        let durations = durations(&data);
+        assert_eq!(durations.extraction_ms, Some(30000));
+        assert_eq!(durations.run_ms, None);
+        assert_eq!(durations.verifications_ms.len(), 2);
+        assert_eq!(durations.verifications_ms.get(&ids[0]), Some(&5000));
+    }
+
+    #[test]
+    fn test_progress() {
+        let (data, _, start) = running_data();
+        let now = start + TimeDelta::seconds(20);
+        let progress = progress(&data, now);
+        assert_eq!(progress.total, data.verification_status.len() - 1);
+        assert_eq!(progress.finished, 2);
+        assert!(progress.percentage > 0.0 && progress.percentage < 100.0);
+        assert_eq!(
+            progress.eta,
+            Some(now + TimeDelta::seconds(10) * (progress.total as i32 - 2))
+        );
+    }
+
+    #[test]
+    fn test_progress_not_running() {
+        let data = AppData::default();
+        let progress = progress(&data, Local::now());
+        assert_eq!(progress.total, 0);
+        assert_eq!(progress.percentage, 0.0);
+        assert!(progress.eta.is_none());
+    }
+}
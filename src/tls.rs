@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Context};
+use axum_server::tls_openssl::OpenSSLConfig;
+use openssl::{
+    pkcs12::Pkcs12,
+    ssl::{SslAcceptor, SslMethod},
+};
+use std::path::{Path, PathBuf};
+
+/// Certificate and key of the HTTPS listener
+///
+/// Either PEM files, or a PKCS#12 keystore with its password in a file (as in `direct-trust`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsConfig {
+    Pem {
+        cert_file: PathBuf,
+        key_file: PathBuf,
+    },
+    Pkcs12 {
+        keystore_file: PathBuf,
+        password_file: PathBuf,
+    },
+}
+
+impl TlsConfig {
+    /// Read the configuration from the variables of `.env`:
+    /// - `APP_TLS_CERT_FILE` and `APP_TLS_KEY_FILE` for PEM files
+    /// - `APP_TLS_PKCS12_FILE` and `APP_TLS_PKCS12_PASSWORD_FILE` for a PKCS#12 keystore
+    ///
+    /// Return `None` if no variable is set (plain HTTP)
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        Self::from_vars(|name| dotenvy::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> anyhow::Result<Option<Self>> {
+        match (
+            var("APP_TLS_CERT_FILE"),
+            var("APP_TLS_KEY_FILE"),
+            var("APP_TLS_PKCS12_FILE"),
+            var("APP_TLS_PKCS12_PASSWORD_FILE"),
+        ) {
+            (None, None, None, None) => Ok(None),
+            (Some(cert), Some(key), None, None) => Ok(Some(Self::Pem {
+                cert_file: PathBuf::from(cert),
+                key_file: PathBuf::from(key),
+            })),
+            (None, None, Some(keystore), Some(password)) => Ok(Some(Self::Pkcs12 {
+                keystore_file: PathBuf::from(keystore),
+                password_file: PathBuf::from(password),
+            })),
+            _ => Err(anyhow!(
+                "TLS configuration not valid: set either APP_TLS_CERT_FILE and APP_TLS_KEY_FILE, \
+                 or APP_TLS_PKCS12_FILE and APP_TLS_PKCS12_PASSWORD_FILE"
+            )),
+        }
+    }
+
+    /// Load the certificate and the key
+    pub fn load(&self) -> anyhow::Result<OpenSSLConfig> {
+        match self {
+            TlsConfig::Pem {
+                cert_file,
+                key_file,
+            } => OpenSSLConfig::from_pem_chain_file(cert_file, key_file).with_context(|| {
+                format!(
+                    "Error loading the certificate {} and the key {}",
+                    cert_file.display(),
+                    key_file.display()
+                )
+            }),
+            TlsConfig::Pkcs12 {
+                keystore_file,
+                password_file,
+            } => load_pkcs12(keystore_file, password_file)
+                .with_context(|| format!("Error loading the keystore {}", keystore_file.display())),
+        }
+    }
+}
+
+fn load_pkcs12(keystore_file: &Path, password_file: &Path) -> anyhow::Result<OpenSSLConfig> {
+    let password = std::fs::read_to_string(password_file).with_context(|| {
+        format!(
+            "Error reading the password file {}",
+            password_file.display()
+        )
+    })?;
+    let parsed = Pkcs12::from_der(&std::fs::read(keystore_file)?)?
+        .parse2(password.lines().next().unwrap_or_default())?;
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+    builder.set_certificate(
+        parsed
+            .cert
+            .as_ref()
+            .ok_or_else(|| anyhow!("No certificate in the keystore"))?,
+    )?;
+    builder.set_private_key(
+        parsed
+            .pkey
+            .as_ref()
+            .ok_or_else(|| anyhow!("No private key in the keystore"))?,
+    )?;
+    for ca in parsed.ca.into_iter().flatten() {
+        builder.add_extra_chain_cert(ca)?;
+    }
+    Ok(OpenSSLConfig::try_from(builder)?)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        hash::MessageDigest,
+        pkey::{PKey, Private},
+        rsa::Rsa,
+        x509::{extension::SubjectAlternativeName, X509Name, X509},
+    };
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    /// Self-signed certificate for `localhost` and `127.0.0.1`
+    pub fn self_signed() -> (X509, PKey<Private>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509Name::builder().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .ip("127.0.0.1")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
+
+    /// Write a self-signed certificate and its key as PEM files in a new temporary directory
+    ///
+    /// Return the directory, the certificate and the configuration
+    pub fn write_pem_files() -> (PathBuf, X509, TlsConfig) {
+        let (cert, key) = self_signed();
+        let dir = std::env::temp_dir().join(format!("verifier-tls-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_file = dir.join("cert.pem");
+        let key_file = dir.join("key.pem");
+        std::fs::write(&cert_file, cert.to_pem().unwrap()).unwrap();
+        std::fs::write(&key_file, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        (
+            dir,
+            cert,
+            TlsConfig::Pem {
+                cert_file,
+                key_file,
+            },
+        )
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let vars = |list: &[(&str, &str)]| {
+            let map = list
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>();
+            TlsConfig::from_vars(move |name| map.get(name).cloned())
+        };
+        assert_eq!(vars(&[]).unwrap(), None);
+        assert_eq!(
+            vars(&[
+                ("APP_TLS_CERT_FILE", "c.pem"),
+                ("APP_TLS_KEY_FILE", "k.pem")
+            ])
+            .unwrap(),
+            Some(TlsConfig::Pem {
+                cert_file: PathBuf::from("c.pem"),
+                key_file: PathBuf::from("k.pem")
+            })
+        );
+        assert!(matches!(
+            vars(&[
+                ("APP_TLS_PKCS12_FILE", "k.p12"),
+                ("APP_TLS_PKCS12_PASSWORD_FILE", "pw.txt")
+            ])
+            .unwrap(),
+            Some(TlsConfig::Pkcs12 { .. })
+        ));
+        assert!(vars(&[("APP_TLS_CERT_FILE", "c.pem")]).is_err());
+        assert!(vars(&[
+            ("APP_TLS_CERT_FILE", "c.pem"),
+            ("APP_TLS_KEY_FILE", "k.pem"),
+            ("APP_TLS_PKCS12_FILE", "k.p12")
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let (dir, _, config) = write_pem_files();
+        assert!(config.load().is_ok());
+
+        let (cert, key) = self_signed();
+        let keystore = Pkcs12::builder()
+            .name("backend")
+            .pkey(&key)
+            .cert(&cert)
+            .build2("secret")
+            .unwrap();
+        std::fs::write(dir.join("keystore.p12"), keystore.to_der().unwrap()).unwrap();
+        std::fs::write(dir.join("pw.txt"), "secret\n").unwrap();
+        let config = TlsConfig::Pkcs12 {
+            keystore_file: dir.join("keystore.p12"),
+            password_file: dir.join("pw.txt"),
+        };
+        assert!(config.load().is_ok());
+        std::fs::write(dir.join("pw.txt"), "wrong").unwrap();
+        assert!(config.load().is_err());
+        assert!(TlsConfig::Pem {
+            cert_file: dir.join("toto.pem"),
+            key_file: dir.join("key.pem")
+        }
+        .load()
+        .is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,6 @@
+#!/bin/bash
+curl --header "Authorization: Bearer $(cat ../data/api_token)" \
+  --request POST \
+  http://localhost:12999/sessions
+
+echo
@@ -1,6 +1,10 @@
 #!/bin/bash
-curl \
+read -s -p "Dataset password: " PASSWORD
+echo
+curl --header "Authorization: Bearer $(cat ../data/api_token)" \
+  --header "Content-Type: application/json" \
   --request POST \
+  --data "{\"password\": \"$PASSWORD\"}" \
   http://localhost:12999/extract
 
-echo
\ No newline at end of file
+echo
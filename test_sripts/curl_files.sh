@@ -0,0 +1,5 @@
+#!/bin/bash
+curl --header "Authorization: Bearer $(cat ../data/api_token)" \
+  "http://localhost:12999/files?path=./datasets"
+
+echo
@@ -0,0 +1,4 @@
+#!/bin/bash
+curl http://localhost:12999/openapi.json
+
+echo
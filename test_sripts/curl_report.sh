@@ -0,0 +1,8 @@
+#!/bin/bash
+# Usage: curl_report.sh [html|pdf]
+FORMAT=${1:-html}
+curl --header "Authorization: Bearer $(cat ../data/api_token)" \
+  --output report.$FORMAT \
+  "http://localhost:12999/report?format=$FORMAT"
+
+echo
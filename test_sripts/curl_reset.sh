@@ -1,5 +1,5 @@
 #!/bin/bash
-curl --header "Content-Type: application/json" \
+curl --header "Authorization: Bearer $(cat ../data/api_token)" --header "Content-Type: application/json" \
   --request POST \
   http://localhost:12999/reset
 
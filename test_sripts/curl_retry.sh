@@ -0,0 +1,16 @@
+#!/bin/bash
+# The password is only used to retry the extraction. Leave it empty to use the last password
+read -s -p "Dataset password: " PASSWORD
+echo
+if [ -z "$PASSWORD" ]; then
+  DATA="{}"
+else
+  DATA="{\"password\": \"$PASSWORD\"}"
+fi
+curl --header "Authorization: Bearer $(cat ../data/api_token)" \
+  --header "Content-Type: application/json" \
+  --request POST \
+  --data "$DATA" \
+  http://localhost:12999/retry
+
+echo
@@ -1,5 +1,5 @@
 #!/bin/bash
-curl \
+curl --header "Authorization: Bearer $(cat ../data/api_token)" \
   --request POST \
   http://localhost:12999/run
 
@@ -0,0 +1,6 @@
+#!/bin/bash
+curl --header "Authorization: Bearer $(cat ../data/api_token)" --header "Content-Type: application/json" \
+  --request POST \
+  http://localhost:12999/run/failed
+
+echo
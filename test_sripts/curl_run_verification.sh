@@ -0,0 +1,7 @@
+#!/bin/bash
+# Usage: curl_run_verification.sh <verification_id>
+curl --header "Authorization: Bearer $(cat ../data/api_token)" --header "Content-Type: application/json" \
+  --request POST \
+  http://localhost:12999/run/$1
+
+echo
@@ -0,0 +1,5 @@
+#!/bin/bash
+# Usage: curl_state_machine.sh [json|dot]
+curl --header "Authorization: Bearer $(cat ../data/api_token)" "http://localhost:12999/state-machine?format=${1:-json}"
+
+echo
@@ -1,4 +1,4 @@
 #!/bin/bash
-curl http://localhost:12999/status
+curl --header "Authorization: Bearer $(cat ../data/api_token)" http://localhost:12999/status
 
 echo
\ No newline at end of file
@@ -0,0 +1,6 @@
+#!/bin/bash
+curl --header "Authorization: Bearer $(cat ../data/api_token)" --request POST \
+  --form "file=@./datasets/Dataset-context-NE_20231124_TT05-20240802_1158.zip" \
+  http://localhost:12999/context-dataset/upload
+
+echo